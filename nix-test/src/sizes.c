@@ -1,3 +1,4 @@
+#include "signal.h"
 #include "sys/socket.h"
 #include "sys/uio.h"
 
@@ -17,6 +18,10 @@
 
 size_t
 size_of(const char* type) {
+    // signal
+    SIZE_OF_T(siginfo_t);
+    SIZE_OF_S(sigevent);
+
     // sys/socket
     SIZE_OF_S(sockaddr_storage);
 
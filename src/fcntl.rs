@@ -1,6 +1,6 @@
 use {Error, Result, NixPath, AsExtStr};
 use errno::Errno;
-use libc::mode_t;
+use libc::{c_int, mode_t};
 use sys::stat::Mode;
 
 pub use self::consts::*;
@@ -103,13 +103,16 @@ pub enum FcntlArg<'a> {
     // TODO: Rest of flags
 }
 
-// TODO: Figure out how to handle value fcntl returns
-pub fn fcntl(fd: Fd, arg: FcntlArg) -> Result<()> {
+/// Performs `arg` on `fd`, returning the value of the call, e.g. the flags for
+/// `F_GETFL`.
+pub fn fcntl(fd: Fd, arg: FcntlArg) -> Result<c_int> {
     use self::FcntlArg::*;
 
     let res = unsafe {
         match arg {
+            F_GETFD => ffi::fcntl(fd, ffi::F_GETFD),
             F_SETFD(flag) => ffi::fcntl(fd, ffi::F_SETFD, flag.bits()),
+            F_GETFL => ffi::fcntl(fd, ffi::F_GETFL),
             F_SETFL(flag) => ffi::fcntl(fd, ffi::F_SETFL, flag.bits()),
             _ => unimplemented!()
         }
@@ -119,9 +122,31 @@ pub fn fcntl(fd: Fd, arg: FcntlArg) -> Result<()> {
         return Err(Error::Sys(Errno::last()));
     }
 
+    Ok(res)
+}
+
+/// Sets or clears the close-on-exec flag of `fd`, leaving its other
+/// descriptor flags untouched
+pub fn set_cloexec(fd: Fd, on: bool) -> Result<()> {
+    let mut flags = FdFlag::from_bits_truncate(try!(fcntl(fd, FcntlArg::F_GETFD)));
+
+    if on {
+        flags.insert(FD_CLOEXEC);
+    } else {
+        flags.remove(FD_CLOEXEC);
+    }
+
+    try!(fcntl(fd, FcntlArg::F_SETFD(flags)));
     Ok(())
 }
 
+/// Returns whether the close-on-exec flag of `fd` is set
+pub fn is_cloexec(fd: Fd) -> Result<bool> {
+    let flags = FdFlag::from_bits_truncate(try!(fcntl(fd, FcntlArg::F_GETFD)));
+
+    Ok(flags.contains(FD_CLOEXEC))
+}
+
 #[cfg(any(target_os = "linux", target_os = "android"))]
 mod consts {
     use libc::c_int;
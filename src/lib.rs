@@ -28,6 +28,9 @@ pub mod fcntl;
 #[cfg(any(target_os = "linux", target_os = "android"))]
 pub mod mount;
 
+#[cfg(unix)]
+pub mod poll;
+
 #[cfg(any(target_os = "linux", target_os = "android"))]
 pub mod sched;
 
@@ -0,0 +1,69 @@
+//! Wait for some event on a set of file descriptors
+//!
+//! [Further reading](http://man7.org/linux/man-pages/man2/poll.2.html)
+use libc::{c_int, c_short};
+use errno::Errno;
+use fcntl::Fd;
+use {Error, Result};
+
+mod ffi {
+    use libc::c_int;
+    use super::PollFd;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub type nfds_t = ::libc::c_ulong;
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub type nfds_t = ::libc::c_uint;
+
+    extern {
+        pub fn poll(fds: *mut PollFd, nfds: nfds_t, timeout: c_int) -> c_int;
+    }
+}
+
+bitflags!(
+    #[repr(C)]
+    flags PollFlags: c_short {
+        const POLLIN   = 0x001,
+        const POLLPRI  = 0x002,
+        const POLLOUT  = 0x004,
+        const POLLERR  = 0x008,
+        const POLLHUP  = 0x010,
+        const POLLNVAL = 0x020,
+    }
+);
+
+/// A file descriptor to poll, along with the events to wait for and,
+/// once `poll` returns, the events that occurred.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PollFd {
+    pub fd: Fd,
+    pub events: PollFlags,
+    pub revents: PollFlags,
+}
+
+impl PollFd {
+    pub fn new(fd: Fd, events: PollFlags) -> PollFd {
+        PollFd {
+            fd: fd,
+            events: events,
+            revents: PollFlags::empty(),
+        }
+    }
+}
+
+/// Waits for one of `fds` to become ready, or for `timeout` milliseconds to
+/// elapse. A negative `timeout` waits forever. Returns the number of entries
+/// of `fds` with a non-empty `revents`.
+pub fn poll(fds: &mut [PollFd], timeout: c_int) -> Result<usize> {
+    let res = unsafe {
+        ffi::poll(fds.as_mut_ptr(), fds.len() as ffi::nfds_t, timeout)
+    };
+
+    if res < 0 {
+        return Err(Error::Sys(Errno::last()));
+    }
+
+    Ok(res as usize)
+}
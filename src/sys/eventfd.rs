@@ -3,6 +3,7 @@ use libc::{c_int, c_uint};
 use errno::Errno;
 use fcntl::Fd;
 use {Error, Result};
+use unistd;
 
 bitflags!(
     flags EventFdFlag: c_int {
@@ -12,22 +13,20 @@ bitflags!(
     }
 );
 
-pub fn eventfd(initval: usize, flags: EventFdFlag) -> Result<Fd> {
-    type F = unsafe extern "C" fn(initval: c_uint, flags: c_int) -> c_int;
+mod ffi {
+    use libc::{c_int, c_uint};
 
     extern {
-        // #[linkage = "extern_weak"]
-        static eventfd: *const ();
-    }
-
-    if eventfd.is_null() {
-        panic!("eventfd unsupported on this platform");
+        pub fn eventfd(initval: c_uint, flags: c_int) -> c_int;
     }
+}
 
-    let res = unsafe {
-        mem::transmute::<*const (), F>(eventfd)(
-            initval as c_uint, flags.bits())
-    };
+/// Creates a file descriptor for event notification, with its counter set to
+/// `initval`
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/eventfd.2.html)
+pub fn eventfd(initval: u32, flags: EventFdFlag) -> Result<Fd> {
+    let res = unsafe { ffi::eventfd(initval as c_uint, flags.bits()) };
 
     if res < 0 {
         return Err(Error::Sys(Errno::last()));
@@ -35,3 +34,24 @@ pub fn eventfd(initval: usize, flags: EventFdFlag) -> Result<Fd> {
 
     Ok(res)
 }
+
+/// Reads the counter of an eventfd, resetting it to zero (or decrementing it
+/// by one with `EFD_SEMAPHORE`)
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/eventfd.2.html)
+pub fn eventfd_read(fd: Fd) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    try!(unistd::read(fd, &mut buf));
+
+    // The kernel always transfers the counter as a native endian u64
+    Ok(unsafe { mem::transmute(buf) })
+}
+
+/// Adds `val` to the counter of an eventfd, waking up any readers
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/eventfd.2.html)
+pub fn eventfd_write(fd: Fd, val: u64) -> Result<()> {
+    let buf: [u8; 8] = unsafe { mem::transmute(val) };
+    try!(unistd::write(fd, &buf));
+    Ok(())
+}
@@ -5,13 +5,14 @@ pub mod epoll;
 #[cfg(any(target_os = "macos", target_os = "ios"))]
 pub mod event;
 
-// Dont' support eventfd for now
 #[cfg(any(target_os = "linux", target_os = "android"))]
 pub mod eventfd;
 
 #[cfg(not(target_os = "ios"))]
 pub mod ioctl;
 
+pub mod pthread;
+
 pub mod signal;
 
 pub mod socket;
@@ -24,6 +25,9 @@ pub mod syscall;
 #[cfg(not(target_os = "ios"))]
 pub mod termios;
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod timerfd;
+
 #[cfg(any(target_os = "linux", target_os = "android"))]
 pub mod utsname;
 
@@ -0,0 +1,150 @@
+//! POSIX threads
+use {Error, Result};
+use errno::Errno;
+use libc::{self, c_int};
+use std::hash;
+
+/// Identifies a thread within the process
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub type Pthread = libc::c_ulong;
+
+/// Identifies a thread within the process
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub type Pthread = usize;
+
+mod ffi {
+    use libc::c_int;
+    use super::Pthread;
+
+    extern {
+        pub fn pthread_self() -> Pthread;
+        pub fn pthread_equal(t1: Pthread, t2: Pthread) -> c_int;
+        pub fn pthread_cancel(thread: Pthread) -> c_int;
+        pub fn pthread_setcancelstate(state: c_int, oldstate: *mut c_int) -> c_int;
+        pub fn pthread_setcanceltype(ty: c_int, oldtype: *mut c_int) -> c_int;
+        pub fn pthread_testcancel();
+    }
+}
+
+/// Returns the identifier of the calling thread
+///
+/// [Further reading](http://man7.org/linux/man-pages/man3/pthread_self.3.html)
+pub fn pthread_self() -> Pthread {
+    unsafe { ffi::pthread_self() }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod consts {
+    use libc::c_int;
+
+    pub const PTHREAD_CANCEL_ENABLE: c_int = 0;
+    pub const PTHREAD_CANCEL_DISABLE: c_int = 1;
+    pub const PTHREAD_CANCEL_DEFERRED: c_int = 0;
+    pub const PTHREAD_CANCEL_ASYNCHRONOUS: c_int = 1;
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod consts {
+    use libc::c_int;
+
+    pub const PTHREAD_CANCEL_ENABLE: c_int = 0x01;
+    pub const PTHREAD_CANCEL_DISABLE: c_int = 0x00;
+    pub const PTHREAD_CANCEL_DEFERRED: c_int = 0x02;
+    pub const PTHREAD_CANCEL_ASYNCHRONOUS: c_int = 0x00;
+}
+
+pub use self::consts::*;
+
+// The pthread functions report failure through their return value rather
+// than errno.
+fn from_pthread(res: c_int) -> Result<()> {
+    if res != 0 {
+        return Err(Error::Sys(Errno::from_i32(res)));
+    }
+
+    Ok(())
+}
+
+/// Requests that `thread` be canceled
+///
+/// With the default deferred cancel type the target only acts on the request
+/// when it reaches a cancellation point: most blocking calls such as `read`,
+/// `sleep` or `pause`, and `pthread_testcancel`. Cancellation unwinds the
+/// target without running Rust destructors, so a thread canceled while
+/// holding a `Mutex` or other lock leaves it held forever. Only cancel
+/// threads that are known to hold no locks or Rust-owned resources.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man3/pthread_cancel.3.html)
+pub fn pthread_cancel(thread: Pthread) -> Result<()> {
+    from_pthread(unsafe { ffi::pthread_cancel(thread) })
+}
+
+/// Enables or disables cancellation of the calling thread, returning
+/// whether it was previously enabled
+///
+/// While disabled, cancel requests stay pending until it is re-enabled.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man3/pthread_setcancelstate.3.html)
+pub fn set_cancel_state(enable: bool) -> Result<bool> {
+    let state = if enable { PTHREAD_CANCEL_ENABLE } else { PTHREAD_CANCEL_DISABLE };
+    let mut old = 0;
+
+    try!(from_pthread(unsafe { ffi::pthread_setcancelstate(state, &mut old) }));
+
+    Ok(old == PTHREAD_CANCEL_ENABLE)
+}
+
+/// Selects asynchronous or, the default, deferred cancellation for the
+/// calling thread, returning whether it was previously asynchronous
+///
+/// Asynchronous cancellation may act at any instruction and is only safe
+/// around code that does not allocate or take locks.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man3/pthread_setcanceltype.3.html)
+pub fn set_cancel_type(asynchronous: bool) -> Result<bool> {
+    let ty = if asynchronous { PTHREAD_CANCEL_ASYNCHRONOUS } else { PTHREAD_CANCEL_DEFERRED };
+    let mut old = 0;
+
+    try!(from_pthread(unsafe { ffi::pthread_setcanceltype(ty, &mut old) }));
+
+    Ok(old == PTHREAD_CANCEL_ASYNCHRONOUS)
+}
+
+/// Acts on a pending cancel request, if cancellation is enabled
+///
+/// This is a cancellation point that does nothing else, for threads that
+/// would otherwise not reach one.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man3/pthread_testcancel.3.html)
+pub fn pthread_testcancel() {
+    unsafe { ffi::pthread_testcancel() }
+}
+
+/// A `Pthread` that can key a `HashMap`.
+///
+/// POSIX only allows thread identifiers to be compared with `pthread_equal`,
+/// which `==` uses; hashing uses the raw value.
+#[derive(Clone, Copy, Debug)]
+pub struct ThreadId(pub Pthread);
+
+impl ThreadId {
+    /// The identifier of the calling thread
+    pub fn current() -> ThreadId {
+        ThreadId(pthread_self())
+    }
+}
+
+impl PartialEq for ThreadId {
+    fn eq(&self, other: &ThreadId) -> bool {
+        unsafe { ffi::pthread_equal(self.0, other.0) != 0 }
+    }
+}
+
+impl Eq for ThreadId {
+}
+
+impl hash::Hash for ThreadId {
+    fn hash<H: hash::Hasher>(&self, s: &mut H) {
+        self.0.hash(s)
+    }
+}
@@ -3,7 +3,8 @@
 
 use libc;
 use errno::Errno;
-use std::mem;
+use std::{fmt, mem, ops, ptr};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering, ATOMIC_BOOL_INIT, ATOMIC_USIZE_INIT};
 use {Error, Result};
 
 pub use libc::consts::os::posix88::{
@@ -43,11 +44,30 @@ pub use self::signal::{
 };
 
 pub use self::signal::SockFlag;
-pub use self::signal::sigset_t;
+pub use self::signal::{
+    SA_NOCLDSTOP,
+    SA_NOCLDWAIT,
+    SA_NODEFER,
+    SA_ONSTACK,
+    SA_RESETHAND,
+    SA_RESTART,
+    SA_SIGINFO,
+};
+pub use self::signal::{sigset_t, siginfo, sigevent};
 
 // This doesn't always exist, but when it does, it's 7
 pub const SIGEMT: libc::c_int = 7;
 
+// Signal specific values of `si_code`, these are the same everywhere
+pub const CLD_EXITED:    libc::c_int = 1;
+pub const CLD_KILLED:    libc::c_int = 2;
+pub const CLD_DUMPED:    libc::c_int = 3;
+pub const CLD_TRAPPED:   libc::c_int = 4;
+pub const CLD_STOPPED:   libc::c_int = 5;
+pub const CLD_CONTINUED: libc::c_int = 6;
+pub const SEGV_MAPERR:   libc::c_int = 1;
+pub const SEGV_ACCERR:   libc::c_int = 2;
+
 #[cfg(any(all(target_os = "linux",
               any(target_arch = "x86",
                   target_arch = "x86_64",
@@ -92,18 +112,38 @@ pub mod signal {
     pub const SIGSYS:       libc::c_int = 31;
     pub const SIGUNUSED:    libc::c_int = 31;
 
+    pub const SI_USER:      libc::c_int = 0;
+    pub const SI_KERNEL:    libc::c_int = 0x80;
+    pub const SI_QUEUE:     libc::c_int = -1;
+    pub const SI_TIMER:     libc::c_int = -2;
+    pub const SI_MESGQ:     libc::c_int = -3;
+    pub const SI_ASYNCIO:   libc::c_int = -4;
+    pub const SI_SIGIO:     libc::c_int = -5;
+    pub const SI_TKILL:     libc::c_int = -6;
+
+    pub const SIG_BLOCK:    libc::c_int = 0;
+    pub const SIG_UNBLOCK:  libc::c_int = 1;
+    pub const SIG_SETMASK:  libc::c_int = 2;
+
     // This definition is not as accurate as it could be, {pid, uid, status} is
     // actually a giant union. Currently we're only interested in these fields,
-    // however.
+    // however. The union is pointer aligned, and the whole structure is padded
+    // out to 128 bytes so that the kernel can fill it in.
     #[repr(C)]
     #[derive(Clone, Copy)]
     pub struct siginfo {
-        si_signo: libc::c_int,
-        si_errno: libc::c_int,
-        si_code: libc::c_int,
+        pub si_signo: libc::c_int,
+        pub si_errno: libc::c_int,
+        pub si_code: libc::c_int,
+        #[cfg(target_pointer_width = "64")]
+        __pad0: libc::c_int,
         pub pid: libc::pid_t,
         pub uid: libc::uid_t,
         pub status: libc::c_int,
+        #[cfg(target_pointer_width = "64")]
+        __pad1: [libc::c_int; 25],
+        #[cfg(target_pointer_width = "32")]
+        __pad1: [libc::c_int; 26],
     }
 
     #[repr(C)]
@@ -128,6 +168,23 @@ pub mod signal {
     pub struct sigset_t {
         __val: [libc::c_ulong; 16],
     }
+
+    pub const SIGEV_SIGNAL: libc::c_int = 0;
+    pub const SIGEV_NONE:   libc::c_int = 1;
+    pub const SIGEV_THREAD: libc::c_int = 2;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct sigevent {
+        // `union sigval`; the pointer member is as wide as the union
+        pub sigev_value: *mut libc::c_void,
+        pub sigev_signo: libc::c_int,
+        pub sigev_notify: libc::c_int,
+        #[cfg(target_pointer_width = "64")]
+        __pad: [libc::c_int; 12],
+        #[cfg(target_pointer_width = "32")]
+        __pad: [libc::c_int; 13],
+    }
 }
 
 #[cfg(all(target_os = "linux",
@@ -170,17 +227,32 @@ pub mod signal {
     pub const SIGXCPU:      libc::c_int = 30;
     pub const SIGFSZ:       libc::c_int = 31;
 
+    pub const SI_USER:      libc::c_int = 0;
+    pub const SI_KERNEL:    libc::c_int = 0x80;
+    pub const SI_QUEUE:     libc::c_int = -1;
+    pub const SI_TIMER:     libc::c_int = -2;
+    pub const SI_MESGQ:     libc::c_int = -3;
+    pub const SI_ASYNCIO:   libc::c_int = -4;
+    pub const SI_SIGIO:     libc::c_int = -5;
+    pub const SI_TKILL:     libc::c_int = -6;
+
+    pub const SIG_BLOCK:    libc::c_int = 1;
+    pub const SIG_UNBLOCK:  libc::c_int = 2;
+    pub const SIG_SETMASK:  libc::c_int = 3;
+
     // This definition is not as accurate as it could be, {pid, uid, status} is
     // actually a giant union. Currently we're only interested in these fields,
     // however.
     #[repr(C)]
+    #[derive(Clone, Copy)]
     pub struct siginfo {
-        si_signo: libc::c_int,
-        si_code: libc::c_int,
-        si_errno: libc::c_int,
+        pub si_signo: libc::c_int,
+        pub si_code: libc::c_int,
+        pub si_errno: libc::c_int,
         pub pid: libc::pid_t,
         pub uid: libc::uid_t,
         pub status: libc::c_int,
+        __pad1: [libc::c_int; 26],
     }
 
     #[repr(C)]
@@ -196,6 +268,20 @@ pub mod signal {
     pub struct sigset_t {
         __val: [libc::c_ulong; 32],
     }
+
+    pub const SIGEV_SIGNAL: libc::c_int = 0;
+    pub const SIGEV_NONE:   libc::c_int = 1;
+    pub const SIGEV_THREAD: libc::c_int = 2;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct sigevent {
+        // `union sigval`; the pointer member is as wide as the union
+        pub sigev_value: *mut libc::c_void,
+        pub sigev_signo: libc::c_int,
+        pub sigev_notify: libc::c_int,
+        __pad: [libc::c_int; 13],
+    }
 }
 
 #[cfg(any(target_os = "macos",
@@ -238,6 +324,16 @@ pub mod signal {
     pub const SIGUSR1:      libc::c_int = 30;
     pub const SIGUSR2:      libc::c_int = 31;
 
+    pub const SI_USER:      libc::c_int = 0x10001;
+    pub const SI_QUEUE:     libc::c_int = 0x10002;
+    pub const SI_TIMER:     libc::c_int = 0x10003;
+    pub const SI_ASYNCIO:   libc::c_int = 0x10004;
+    pub const SI_MESGQ:     libc::c_int = 0x10005;
+
+    pub const SIG_BLOCK:    libc::c_int = 1;
+    pub const SIG_UNBLOCK:  libc::c_int = 2;
+    pub const SIG_SETMASK:  libc::c_int = 3;
+
     #[cfg(any(target_os = "macos", target_os = "ios"))]
     pub type sigset_t = u32;
     #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
@@ -247,7 +343,7 @@ pub mod signal {
     }
 
     // This structure has more fields, but we're not all that interested in
-    // them.
+    // them. They are kept as padding so that the kernel can fill it in.
     #[repr(C)]
     #[derive(Clone, Copy)]
     pub struct siginfo {
@@ -257,6 +353,8 @@ pub mod signal {
         pub pid: libc::pid_t,
         pub uid: libc::uid_t,
         pub status: libc::c_int,
+        pub addr: *mut libc::c_void,
+        __pad1: [libc::c_ulong; 9],
     }
 
     #[cfg(any(target_os = "macos", target_os = "ios"))]
@@ -277,6 +375,26 @@ pub mod signal {
         pub sa_mask: sigset_t,
     }
 
+    pub const SIGEV_NONE:   libc::c_int = 0;
+    pub const SIGEV_SIGNAL: libc::c_int = 1;
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    pub const SIGEV_THREAD: libc::c_int = 3;
+    #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+    pub const SIGEV_THREAD: libc::c_int = 2;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct sigevent {
+        pub sigev_notify: libc::c_int,
+        pub sigev_signo: libc::c_int,
+        // `union sigval`; the pointer member is as wide as the union
+        pub sigev_value: *mut libc::c_void,
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        __pad: [*mut libc::c_void; 2],
+        #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+        __pad: [libc::c_long; 8],
+    }
+
 }
 
 mod ffi {
@@ -292,6 +410,21 @@ mod ffi {
         pub fn sigaddset(set: *mut sigset_t, signum: libc::c_int) -> libc::c_int;
         pub fn sigdelset(set: *mut sigset_t, signum: libc::c_int) -> libc::c_int;
         pub fn sigemptyset(set: *mut sigset_t) -> libc::c_int;
+        pub fn sigfillset(set: *mut sigset_t) -> libc::c_int;
+        pub fn sigismember(set: *const sigset_t, signum: libc::c_int) -> libc::c_int;
+
+        pub fn sigprocmask(how: libc::c_int,
+                           set: *const sigset_t,
+                           oldset: *mut sigset_t) -> libc::c_int;
+
+        pub fn pthread_sigmask(how: libc::c_int,
+                               set: *const sigset_t,
+                               oldset: *mut sigset_t) -> libc::c_int;
+
+        pub fn sigwait(set: *const sigset_t, sig: *mut libc::c_int) -> libc::c_int;
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        pub fn sigwaitinfo(set: *const sigset_t, info: *mut super::signal::siginfo) -> libc::c_int;
 
         pub fn kill(pid: libc::pid_t, signum: libc::c_int) -> libc::c_int;
     }
@@ -305,6 +438,11 @@ pub struct SigSet {
 pub type SigNum = libc::c_int;
 
 impl SigSet {
+    /// Same as `SigSet::empty()`
+    pub fn new() -> SigSet {
+        SigSet::empty()
+    }
+
     pub fn empty() -> SigSet {
         let mut sigset = unsafe { mem::uninitialized::<sigset_t>() };
         let _ = unsafe { ffi::sigemptyset(&mut sigset as *mut sigset_t) };
@@ -312,6 +450,48 @@ impl SigSet {
         SigSet { sigset: sigset }
     }
 
+    /// A set containing every signal.
+    pub fn all() -> SigSet {
+        let mut sigset = unsafe { mem::uninitialized::<sigset_t>() };
+        let _ = unsafe { ffi::sigfillset(&mut sigset as *mut sigset_t) };
+
+        SigSet { sigset: sigset }
+    }
+
+    /// A set containing only the given signals. Invalid signal numbers are
+    /// ignored.
+    pub fn from_signals(signals: &[SigNum]) -> SigSet {
+        let mut set = SigSet::empty();
+
+        for &signum in signals {
+            let _ = set.add(signum);
+        }
+
+        set
+    }
+
+    /// A set containing every signal but the given ones, e.g. to block
+    /// everything except `SIGINT` and `SIGTERM`.
+    pub fn all_except(signals: &[SigNum]) -> SigSet {
+        let mut set = SigSet::all();
+
+        for &signum in signals {
+            let _ = set.remove(signum);
+        }
+
+        set
+    }
+
+    /// Wraps a `sigset_t` received from foreign code.
+    pub fn from_sigset_t(raw: sigset_t) -> SigSet {
+        SigSet { sigset: raw }
+    }
+
+    /// Unwraps the `sigset_t`, e.g. to pass it to foreign code.
+    pub fn into_sigset_t(self) -> sigset_t {
+        self.sigset
+    }
+
     pub fn add(&mut self, signum: SigNum) -> Result<()> {
         let res = unsafe { ffi::sigaddset(&mut self.sigset as *mut sigset_t, signum) };
 
@@ -331,6 +511,598 @@ impl SigSet {
 
         Ok(())
     }
+
+    /// Returns whether `signum` is a member of the set. Invalid signal numbers
+    /// are never members.
+    pub fn contains(&self, signum: SigNum) -> bool {
+        let res = unsafe { ffi::sigismember(&self.sigset as *const sigset_t, signum) };
+
+        res == 1
+    }
+
+    /// Waits for one of the signals in the set to become pending, and returns
+    /// it after removing it from the pending signals. The signals are blocked
+    /// while waiting and the mask of the calling thread is restored before
+    /// returning, so a signal that was not blocked before stays unblocked.
+    ///
+    /// [Further reading](http://man7.org/linux/man-pages/man3/sigwait.3.html)
+    pub fn wait(&self) -> Result<SigNum> {
+        let _guard = try!(thread_block_scoped(self));
+        let mut signum: SigNum = 0;
+
+        let res = unsafe { ffi::sigwait(&self.sigset as *const sigset_t, &mut signum) };
+
+        // sigwait returns the error rather than setting errno
+        if res != 0 {
+            return Err(Error::Sys(Errno::from_i32(res)));
+        }
+
+        Ok(signum)
+    }
+}
+
+impl Default for SigSet {
+    fn default() -> SigSet {
+        SigSet::empty()
+    }
+}
+
+/// Sets are equal when they contain the same signals.
+impl PartialEq for SigSet {
+    fn eq(&self, other: &SigSet) -> bool {
+        (1..max_signal() + 1).all(|signum| self.contains(signum) == other.contains(signum))
+    }
+}
+
+impl Eq for SigSet {}
+
+/// `set | SIGTERM` is a copy of `set` with `SIGTERM` added. An invalid
+/// signal number leaves the set unchanged.
+impl ops::BitOr<SigNum> for SigSet {
+    type Output = SigSet;
+
+    fn bitor(mut self, signum: SigNum) -> SigSet {
+        let _ = self.add(signum);
+        self
+    }
+}
+
+impl SigSet {
+    /// Iterates over the signals in the set, in increasing order, including
+    /// real-time signals.
+    pub fn iter(&self) -> SigSetIter {
+        SigSetIter { set: *self, next: 1 }
+    }
+
+    /// Compares two sets, returning the signals only in `self` and the
+    /// signals only in `other`, e.g. to find out how an intended mask
+    /// differs from the actual one.
+    pub fn difference(&self, other: &SigSet) -> (SigSet, SigSet) {
+        let only_in_self = self.iter().filter(|&signum| !other.contains(signum));
+        let only_in_other = other.iter().filter(|&signum| !self.contains(signum));
+
+        (SigSet::from_signals(&only_in_self.collect::<Vec<_>>()),
+         SigSet::from_signals(&only_in_other.collect::<Vec<_>>()))
+    }
+}
+
+/// Iterator over the signals of a `SigSet`, see `SigSet::iter`.
+pub struct SigSetIter {
+    set: SigSet,
+    next: SigNum,
+}
+
+impl Iterator for SigSetIter {
+    type Item = SigNum;
+
+    fn next(&mut self) -> Option<SigNum> {
+        while self.next <= max_signal() {
+            let signum = self.next;
+            self.next += 1;
+
+            if self.set.contains(signum) {
+                return Some(signum);
+            }
+        }
+
+        None
+    }
+}
+
+/// Shows the signals by name, with real-time signals as `SIGRTMIN+n`.
+impl fmt::Debug for SigSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{{"));
+
+        for (i, signum) in self.iter().enumerate() {
+            if i > 0 {
+                try!(write!(f, ", "));
+            }
+
+            try!(write!(f, "{}", describe_signal(signum)));
+        }
+
+        write!(f, "}}")
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod rt {
+    use libc;
+
+    extern {
+        pub fn __libc_current_sigrtmin() -> libc::c_int;
+        pub fn __libc_current_sigrtmax() -> libc::c_int;
+    }
+}
+
+/// The lowest real-time signal available to applications. This is above
+/// the kernel's `SIGRTMIN`, as the C library reserves the first few for its
+/// own use.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn sigrtmin() -> SigNum {
+    unsafe { rt::__libc_current_sigrtmin() }
+}
+
+/// The highest real-time signal.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn sigrtmax() -> SigNum {
+    unsafe { rt::__libc_current_sigrtmax() }
+}
+
+/// Returns the real-time signal `SIGRTMIN+n`. Panics if it would be above
+/// `sigrtmax()`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn rt_signal(n: SigNum) -> SigNum {
+    let signum = sigrtmin() + n;
+    assert!(n >= 0 && signum <= sigrtmax(), "real-time signal out of range; n={}", n);
+    signum
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn max_signal() -> SigNum {
+    sigrtmax()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn max_signal() -> SigNum {
+    // NSIG - 1, there are no real-time signals
+    31
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn rt_signal_offset(signum: SigNum) -> Option<SigNum> {
+    if signum >= sigrtmin() && signum <= sigrtmax() {
+        Some(signum - sigrtmin())
+    } else {
+        None
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn rt_signal_offset(_: SigNum) -> Option<SigNum> {
+    None
+}
+
+// The name of the signal, `SIGRTMIN+n` for real-time signals, or the number
+// for anything else
+fn describe_signal(signum: SigNum) -> String {
+    if let Some(name) = signal_name(signum) {
+        return name.to_string();
+    }
+
+    match rt_signal_offset(signum) {
+        Some(0) => "SIGRTMIN".to_string(),
+        Some(n) => format!("SIGRTMIN+{}", n),
+        None => signum.to_string(),
+    }
+}
+
+/// How `pthread_sigmask` and `sigprocmask` combine the given set with the
+/// current mask.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SigMaskHow {
+    /// Add the signals in the set to the mask
+    SIG_BLOCK = signal::SIG_BLOCK,
+    /// Remove the signals in the set from the mask
+    SIG_UNBLOCK = signal::SIG_UNBLOCK,
+    /// Replace the mask with the set
+    SIG_SETMASK = signal::SIG_SETMASK,
+}
+
+/// Changes the signal mask of the calling thread, returning the previous
+/// mask. With a `set` of `None` the mask is only read and `how` is ignored.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man3/pthread_sigmask.3.html)
+pub fn pthread_sigmask(how: SigMaskHow, set: Option<&SigSet>) -> Result<SigSet> {
+    let mut oldset = unsafe { mem::uninitialized::<sigset_t>() };
+
+    let set = match set {
+        Some(set) => &set.sigset as *const sigset_t,
+        None => ptr::null(),
+    };
+
+    let res = unsafe {
+        ffi::pthread_sigmask(how as libc::c_int, set, &mut oldset as *mut sigset_t)
+    };
+
+    // pthread_sigmask returns the error rather than setting errno
+    if res != 0 {
+        return Err(Error::Sys(Errno::from_i32(res)));
+    }
+
+    Ok(SigSet { sigset: oldset })
+}
+
+/// Replaces the signal mask of the calling thread with `set`, returning the
+/// previous mask.
+pub fn set_mask(set: &SigSet) -> Result<SigSet> {
+    pthread_sigmask(SigMaskHow::SIG_SETMASK, Some(set))
+}
+
+/// Returns the signal mask of the calling thread.
+pub fn get_mask() -> Result<SigSet> {
+    pthread_sigmask(SigMaskHow::SIG_BLOCK, None)
+}
+
+/// Changes the signal mask of the process, returning the previous mask.
+/// With a `set` of `None` the mask is only read and `how` is ignored.
+///
+/// Only use this in single-threaded programs, its effect in a threaded one
+/// is unspecified; `pthread_sigmask` is the thread safe equivalent.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/sigprocmask.2.html)
+pub fn sigprocmask(how: SigMaskHow, set: Option<&SigSet>) -> Result<SigSet> {
+    let mut oldset = unsafe { mem::uninitialized::<sigset_t>() };
+
+    let set = match set {
+        Some(set) => &set.sigset as *const sigset_t,
+        None => ptr::null(),
+    };
+
+    let res = unsafe {
+        ffi::sigprocmask(how as libc::c_int, set, &mut oldset as *mut sigset_t)
+    };
+
+    if res < 0 {
+        return Err(Error::Sys(Errno::last()));
+    }
+
+    Ok(SigSet { sigset: oldset })
+}
+
+/// Blocks `set` on the calling thread until the returned guard is dropped,
+/// at which point the previous mask is restored.
+pub fn thread_block_scoped(set: &SigSet) -> Result<SigMaskGuard> {
+    let old = try!(pthread_sigmask(SigMaskHow::SIG_BLOCK, Some(set)));
+
+    Ok(SigMaskGuard { old: old })
+}
+
+/// Restores the signal mask of the calling thread when dropped, see
+/// `thread_block_scoped`.
+#[must_use]
+pub struct SigMaskGuard {
+    old: SigSet
+}
+
+impl Drop for SigMaskGuard {
+    fn drop(&mut self) {
+        let _ = pthread_sigmask(SigMaskHow::SIG_SETMASK, Some(&self.old));
+    }
+}
+
+/// Blocks `SIGTERM` and `SIGINT` on the calling thread and waits for either
+/// of them, returning the one that arrived. They stay blocked afterwards.
+///
+/// A process-directed signal is delivered to any thread that does not block
+/// it, so the signals must be blocked in every thread for this to see them,
+/// typically by blocking them in the main thread before spawning any others,
+/// which inherit the mask. Calling this on a thread that already blocks them
+/// is fine.
+pub fn wait_for_shutdown() -> Result<SigNum> {
+    let set = SigSet::from_signals(&[SIGTERM, SIGINT]);
+    try!(pthread_sigmask(SigMaskHow::SIG_BLOCK, Some(&set)));
+
+    set.wait()
+}
+
+/// Information about a delivered signal, as handed to a handler installed
+/// with `SigAction::new_siginfo`.
+#[derive(Clone, Copy)]
+pub struct SigInfo {
+    info: siginfo
+}
+
+impl SigInfo {
+    /// Copies the `siginfo` the kernel passed to a `SA_SIGINFO` handler.
+    pub unsafe fn from_raw(info: *const siginfo) -> SigInfo {
+        SigInfo { info: *info }
+    }
+
+    pub fn signo(&self) -> SigNum {
+        self.info.si_signo
+    }
+
+    pub fn errno(&self) -> libc::c_int {
+        self.info.si_errno
+    }
+
+    pub fn code(&self) -> libc::c_int {
+        self.info.si_code
+    }
+
+    /// The pid of the child that changed state. Only meaningful for
+    /// `SIGCHLD`.
+    pub fn child_pid(&self) -> libc::pid_t {
+        self.info.pid
+    }
+
+    /// The exit code of the child, or the signal that caused it to change
+    /// state, depending on the `CLD_*` value of `code()`. Only meaningful for
+    /// `SIGCHLD`.
+    pub fn status(&self) -> libc::c_int {
+        self.info.status
+    }
+
+    /// The pid of the process that sent the signal. Only meaningful when it
+    /// was sent by `kill` or `sigqueue`.
+    pub fn pid(&self) -> libc::pid_t {
+        self.info.pid
+    }
+
+    /// The real uid of the process that sent the signal. Only meaningful when
+    /// it was sent by `kill` or `sigqueue`.
+    pub fn uid(&self) -> libc::uid_t {
+        self.info.uid
+    }
+
+    /// The address that caused the fault. Only meaningful for `SIGSEGV`,
+    /// `SIGBUS`, `SIGILL` and `SIGFPE`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn addr(&self) -> *mut libc::c_void {
+        // `si_addr` lives at the start of the union, just like `si_pid`. The
+        // struct only has `c_int` fields, so a copy of it is not necessarily
+        // pointer aligned and the pointer is copied out bytewise.
+        let mut addr: *mut libc::c_void = ptr::null_mut();
+
+        unsafe {
+            ptr::copy_nonoverlapping(&self.info.pid as *const libc::pid_t as *const u8,
+                                     &mut addr as *mut *mut libc::c_void as *mut u8,
+                                     mem::size_of::<*mut libc::c_void>());
+        }
+
+        addr
+    }
+
+    /// The address that caused the fault. Only meaningful for `SIGSEGV`,
+    /// `SIGBUS`, `SIGILL` and `SIGFPE`.
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub fn addr(&self) -> *mut libc::c_void {
+        self.info.addr
+    }
+
+    /// The id of the POSIX timer that expired, as returned by
+    /// `timer_create`. Only meaningful when `reason()` is `SigCode::Timer`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn timer_id(&self) -> libc::c_int {
+        // The `_timer` branch of the union starts with `si_tid`, where the
+        // `_kill` branch has `si_pid`
+        self.info.pid as libc::c_int
+    }
+
+    /// The number of extra expirations of the timer since its signal was
+    /// queued. Only meaningful when `reason()` is `SigCode::Timer`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn overrun(&self) -> libc::c_int {
+        // `si_overrun` follows `si_tid`, in place of `si_uid`
+        self.info.uid as libc::c_int
+    }
+
+    /// Why the signal was sent, decoded from `code()`.
+    pub fn reason(&self) -> SigCode {
+        SigCode::from_raw(self.signo(), self.code())
+    }
+
+    /// Describes the signal in one line, e.g. `SIGUSR1 queued by sigqueue
+    /// (pid 42, uid 1000)`.
+    ///
+    /// `SigInfo` is plain data, so a handler can copy it out and leave the
+    /// formatting, which allocates, to a context where that is safe.
+    pub fn describe(&self) -> String {
+        let name = signal_name(self.signo()).unwrap_or("unknown signal");
+        let reason = self.reason();
+
+        match reason {
+            SigCode::User | SigCode::Queue | SigCode::Tkill => {
+                format!("{} {} (pid {}, uid {})",
+                        name, reason.description(), self.pid(), self.uid())
+            }
+            SigCode::Child(_) => {
+                format!("{} {} (pid {}, status {})",
+                        name, reason.description(), self.child_pid(), self.status())
+            }
+            SigCode::Fault(_) => {
+                format!("{} {} (address {:p})", name, reason.description(), self.addr())
+            }
+            _ => format!("{} {}", name, reason.description()),
+        }
+    }
+}
+
+impl fmt::Debug for SigInfo {
+    // Unlike `describe`, only allocates for signals without a name
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "SigInfo {{ signo: "));
+
+        match signal_name(self.signo()) {
+            Some(name) => try!(write!(f, "{}", name)),
+            None => try!(write!(f, "{}", describe_signal(self.signo()))),
+        }
+
+        let reason = self.reason();
+        try!(write!(f, ", errno: {}, reason: {:?}", self.errno(), reason));
+
+        match reason {
+            SigCode::User | SigCode::Queue | SigCode::Tkill => {
+                try!(write!(f, ", pid: {}, uid: {}", self.pid(), self.uid()));
+            }
+            SigCode::Child(_) => {
+                try!(write!(f, ", pid: {}, status: {}", self.child_pid(), self.status()));
+            }
+            SigCode::Fault(_) => {
+                try!(write!(f, ", addr: {:p}", self.addr()));
+            }
+            _ => {}
+        }
+
+        write!(f, " }}")
+    }
+}
+
+/// The reason a signal was sent, as found in `si_code`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SigCode {
+    /// Sent by `kill` or `raise`
+    User,
+    /// Sent by the kernel
+    Kernel,
+    /// Sent by `sigqueue`
+    Queue,
+    /// A POSIX timer expired
+    Timer,
+    /// A message arrived on an empty message queue
+    MesgQ,
+    /// Asynchronous I/O completed
+    AsyncIo,
+    /// A queued `SIGIO`
+    SigIo,
+    /// Sent by `tkill` or `tgkill`
+    Tkill,
+    /// A `SIGCHLD` child state change, one of the `CLD_*` values
+    Child(libc::c_int),
+    /// A fault raised by the hardware, e.g. one of the `SEGV_*` values
+    Fault(libc::c_int),
+    /// Anything else
+    Other(libc::c_int),
+}
+
+impl SigCode {
+    pub fn from_raw(signo: SigNum, code: libc::c_int) -> SigCode {
+        match code {
+            signal::SI_USER => SigCode::User,
+            signal::SI_QUEUE => SigCode::Queue,
+            signal::SI_TIMER => SigCode::Timer,
+            signal::SI_MESGQ => SigCode::MesgQ,
+            signal::SI_ASYNCIO => SigCode::AsyncIo,
+            code => match from_os_code(code) {
+                Some(reason) => reason,
+                None if code > 0 && signo == SIGCHLD => SigCode::Child(code),
+                None if code > 0 && is_fault(signo) => SigCode::Fault(code),
+                None => SigCode::Other(code),
+            }
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match *self {
+            SigCode::User => "sent by kill",
+            SigCode::Kernel => "sent by the kernel",
+            SigCode::Queue => "queued by sigqueue",
+            SigCode::Timer => "from an expired timer",
+            SigCode::MesgQ => "from a message queue",
+            SigCode::AsyncIo => "from completed asynchronous I/O",
+            SigCode::SigIo => "from queued SIGIO",
+            SigCode::Tkill => "sent by tkill",
+            SigCode::Child(CLD_EXITED) => "from a child that exited",
+            SigCode::Child(CLD_KILLED) => "from a child that was killed",
+            SigCode::Child(CLD_DUMPED) => "from a child that dumped core",
+            SigCode::Child(CLD_TRAPPED) => "from a traced child that trapped",
+            SigCode::Child(CLD_STOPPED) => "from a child that stopped",
+            SigCode::Child(CLD_CONTINUED) => "from a child that continued",
+            SigCode::Child(_) => "from a child",
+            SigCode::Fault(_) => "from a fault",
+            SigCode::Other(_) => "for an unknown reason",
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn from_os_code(code: libc::c_int) -> Option<SigCode> {
+    match code {
+        signal::SI_KERNEL => Some(SigCode::Kernel),
+        signal::SI_SIGIO => Some(SigCode::SigIo),
+        signal::SI_TKILL => Some(SigCode::Tkill),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn from_os_code(_: libc::c_int) -> Option<SigCode> {
+    None
+}
+
+fn is_fault(signo: SigNum) -> bool {
+    signo == SIGSEGV || signo == SIGBUS || signo == SIGILL || signo == SIGFPE
+}
+
+static SIGNAL_NAMES: [(SigNum, &'static str); 29] = [
+    (SIGHUP,    "SIGHUP"),
+    (SIGINT,    "SIGINT"),
+    (SIGQUIT,   "SIGQUIT"),
+    (SIGILL,    "SIGILL"),
+    (SIGTRAP,   "SIGTRAP"),
+    (SIGABRT,   "SIGABRT"),
+    (SIGBUS,    "SIGBUS"),
+    (SIGFPE,    "SIGFPE"),
+    (SIGKILL,   "SIGKILL"),
+    (SIGUSR1,   "SIGUSR1"),
+    (SIGSEGV,   "SIGSEGV"),
+    (SIGUSR2,   "SIGUSR2"),
+    (SIGPIPE,   "SIGPIPE"),
+    (SIGALRM,   "SIGALRM"),
+    (SIGTERM,   "SIGTERM"),
+    (SIGCHLD,   "SIGCHLD"),
+    (SIGCONT,   "SIGCONT"),
+    (SIGSTOP,   "SIGSTOP"),
+    (SIGTSTP,   "SIGTSTP"),
+    (SIGTTIN,   "SIGTTIN"),
+    (SIGTTOU,   "SIGTTOU"),
+    (SIGURG,    "SIGURG"),
+    (SIGXCPU,   "SIGXCPU"),
+    (SIGXFSZ,   "SIGXFSZ"),
+    (SIGVTALRM, "SIGVTALRM"),
+    (SIGPROF,   "SIGPROF"),
+    (SIGWINCH,  "SIGWINCH"),
+    (SIGIO,     "SIGIO"),
+    (SIGSYS,    "SIGSYS"),
+];
+
+/// Returns the symbolic name of `signum`, e.g. `"SIGINT"`, or `None` for a
+/// signal this module does not know about.
+pub fn signal_name(signum: SigNum) -> Option<&'static str> {
+    SIGNAL_NAMES.iter()
+        .find(|&&(num, _)| num == signum)
+        .map(|&(_, name)| name)
+}
+
+/// Parses a signal given as its full name, e.g. `"SIGTERM"`, its name without
+/// the `SIG` prefix, e.g. `"TERM"`, or its number, e.g. `"15"`. The reverse
+/// of `signal_name`. Fails with `EINVAL` for anything else.
+pub fn parse_signal(s: &str) -> Result<SigNum> {
+    if let Ok(signum) = s.parse::<SigNum>() {
+        if signum > 0 && signum <= max_signal() {
+            return Ok(signum);
+        }
+
+        return Err(Error::Sys(Errno::EINVAL));
+    }
+
+    SIGNAL_NAMES.iter()
+        .find(|&&(_, name)| name == s || &name[3..] == s)
+        .map(|&(num, _)| num)
+        .ok_or(Error::Sys(Errno::EINVAL))
 }
 
 type sigaction_t = self::signal::sigaction;
@@ -348,6 +1120,37 @@ impl SigAction {
 
         SigAction { sigaction: s }
     }
+
+    /// Creates an action whose handler also receives the `siginfo` describing
+    /// the signal. `SA_SIGINFO` is added to `flags`.
+    pub fn new_siginfo(handler: extern fn(libc::c_int, *mut siginfo, *mut libc::c_void),
+                       flags: SockFlag, mask: SigSet) -> SigAction {
+        let mut s = unsafe { mem::uninitialized::<sigaction_t>() };
+        s.sa_handler = unsafe { mem::transmute(handler) };
+        s.sa_flags = flags | signal::SA_SIGINFO;
+        s.sa_mask = mask.sigset;
+
+        SigAction { sigaction: s }
+    }
+
+    /// Creates an action that handles the signal once: the disposition is
+    /// reset to the default when the handler is entered, so a second signal
+    /// gets the default behavior.
+    pub fn oneshot(handler: extern fn(libc::c_int), mask: SigSet) -> SigAction {
+        SigAction::new(handler, signal::SA_RESETHAND, mask)
+    }
+
+    /// Creates an action for `SIGCHLD` that also stops terminated children
+    /// from becoming zombies (`SA_NOCLDWAIT`), for processes that never wait
+    /// for them. `waitpid` then blocks until every child has terminated and
+    /// fails with `ECHILD`.
+    pub fn no_child_wait(handler: extern fn(libc::c_int), mask: SigSet) -> SigAction {
+        SigAction::new(handler, signal::SA_NOCLDWAIT, mask)
+    }
+
+    pub fn flags(&self) -> SockFlag {
+        self.sigaction.sa_flags
+    }
 }
 
 pub fn sigaction(signum: SigNum, sigaction: &SigAction) -> Result<SigAction> {
@@ -364,6 +1167,23 @@ pub fn sigaction(signum: SigNum, sigaction: &SigAction) -> Result<SigAction> {
     Ok(SigAction { sigaction: oldact })
 }
 
+/// Returns the current action for `signum` without changing it
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/sigaction.2.html)
+pub fn sigaction_query(signum: SigNum) -> Result<SigAction> {
+    let mut oldact = unsafe { mem::uninitialized::<sigaction_t>() };
+
+    let res = unsafe {
+        ffi::sigaction(signum, ptr::null(), &mut oldact as *mut sigaction_t)
+    };
+
+    if res < 0 {
+        return Err(Error::Sys(Errno::last()));
+    }
+
+    Ok(SigAction { sigaction: oldact })
+}
+
 pub fn kill(pid: libc::pid_t, signum: SigNum) -> Result<()> {
     let res = unsafe { ffi::kill(pid, signum) };
 
@@ -373,3 +1193,384 @@ pub fn kill(pid: libc::pid_t, signum: SigNum) -> Result<()> {
 
     Ok(())
 }
+
+/// A blocking iterator over the signals of a set, as they are delivered.
+///
+/// The set is blocked on the thread creating the stream and stays blocked.
+/// A signal sent to the process is only reliably received by the stream if
+/// every other thread has it blocked too, which is easiest done by creating
+/// the stream before spawning any threads.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/sigwaitinfo.2.html)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub struct SignalStream {
+    set: SigSet,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SignalStream {
+    pub fn new(set: SigSet) -> Result<SignalStream> {
+        try!(pthread_sigmask(SigMaskHow::SIG_BLOCK, Some(&set)));
+
+        Ok(SignalStream { set: set })
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Iterator for SignalStream {
+    type Item = Result<SigInfo>;
+
+    /// Waits for the next signal, never returns `None`
+    fn next(&mut self) -> Option<Result<SigInfo>> {
+        loop {
+            let mut info = unsafe { mem::zeroed::<siginfo>() };
+            let res = unsafe { ffi::sigwaitinfo(&self.set.sigset as *const sigset_t, &mut info) };
+
+            if res < 0 {
+                match Errno::last() {
+                    Errno::EINTR => continue,
+                    errno => return Some(Err(Error::Sys(errno))),
+                }
+            }
+
+            return Some(Ok(SigInfo { info: info }));
+        }
+    }
+}
+
+/// Checks whether a process with the given pid exists, without sending it a
+/// signal. A process we lack the permission to signal (`EPERM`) exists.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/kill.2.html)
+pub fn process_exists(pid: libc::pid_t) -> Result<bool> {
+    match kill(pid, 0) {
+        Ok(()) => Ok(true),
+        Err(Error::Sys(Errno::EPERM)) => Ok(true),
+        Err(Error::Sys(Errno::ESRCH)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/*
+ *
+ * ===== Signal registry =====
+ *
+ */
+
+// Number of signals the registry can hold before `drain` is called, must be a
+// power of two
+const SIGNAL_QUEUE_LEN: usize = 64;
+
+// A bounded multi-producer, single-consumer queue. Every cell carries a
+// sequence number telling whether it is free for the producer at `pos`
+// (`seq == pos`) or holds the value for the consumer at `pos` (`seq ==
+// pos + 1`). Producers only use atomic operations, so it is safe to push from
+// a signal handler.
+struct SignalQueue {
+    seqs: Vec<AtomicUsize>,
+    vals: Vec<AtomicUsize>,
+    enqueue_pos: AtomicUsize,
+    dequeue_pos: AtomicUsize,
+}
+
+impl SignalQueue {
+    fn new() -> SignalQueue {
+        SignalQueue {
+            seqs: (0..SIGNAL_QUEUE_LEN).map(|i| AtomicUsize::new(i)).collect(),
+            vals: (0..SIGNAL_QUEUE_LEN).map(|_| AtomicUsize::new(0)).collect(),
+            enqueue_pos: AtomicUsize::new(0),
+            dequeue_pos: AtomicUsize::new(0),
+        }
+    }
+
+    // Returns false, dropping `val`, if the queue is full
+    fn push(&self, val: usize) -> bool {
+        loop {
+            let pos = self.enqueue_pos.load(Ordering::SeqCst);
+            let cell = pos & (SIGNAL_QUEUE_LEN - 1);
+            let dif = self.seqs[cell].load(Ordering::SeqCst) as isize - pos as isize;
+
+            if dif == 0 {
+                if self.enqueue_pos.compare_and_swap(pos, pos + 1, Ordering::SeqCst) == pos {
+                    self.vals[cell].store(val, Ordering::SeqCst);
+                    self.seqs[cell].store(pos + 1, Ordering::SeqCst);
+                    return true;
+                }
+            } else if dif < 0 {
+                return false;
+            }
+        }
+    }
+
+    // Must only be called by one thread at a time
+    fn pop(&self) -> Option<usize> {
+        let pos = self.dequeue_pos.load(Ordering::SeqCst);
+        let cell = pos & (SIGNAL_QUEUE_LEN - 1);
+
+        if self.seqs[cell].load(Ordering::SeqCst) != pos + 1 {
+            return None;
+        }
+
+        let val = self.vals[cell].load(Ordering::SeqCst);
+        self.seqs[cell].store(pos + SIGNAL_QUEUE_LEN, Ordering::SeqCst);
+        self.dequeue_pos.store(pos + 1, Ordering::SeqCst);
+
+        Some(val)
+    }
+}
+
+// The queue the trampoline pushes to. It is allocated by the first registry
+// and never freed, as a handler may still be running on another thread after
+// the registry is dropped.
+static SIGNAL_QUEUE: AtomicUsize = ATOMIC_USIZE_INIT;
+
+static REGISTRY_TAKEN: AtomicBool = ATOMIC_BOOL_INIT;
+
+fn signal_queue() -> &'static SignalQueue {
+    let mut ptr = SIGNAL_QUEUE.load(Ordering::SeqCst);
+
+    if ptr == 0 {
+        let queue = Box::new(SignalQueue::new());
+        let queue_ptr = &*queue as *const SignalQueue as usize;
+
+        ptr = SIGNAL_QUEUE.compare_and_swap(0, queue_ptr, Ordering::SeqCst);
+
+        if ptr == 0 {
+            mem::forget(queue);
+            ptr = queue_ptr;
+        }
+    }
+
+    unsafe { &*(ptr as *const SignalQueue) }
+}
+
+extern fn registry_trampoline(signum: libc::c_int, _: *mut siginfo, _: *mut libc::c_void) {
+    let ptr = SIGNAL_QUEUE.load(Ordering::SeqCst);
+
+    if ptr != 0 {
+        let queue = unsafe { &*(ptr as *const SignalQueue) };
+        let _ = queue.push(signum as usize);
+    }
+}
+
+/// Routes signals to a queue that is drained from normal code, instead of
+/// handling each of them in its own `extern fn`.
+///
+/// A single `SA_SIGINFO` handler is installed for every added signal, which
+/// only records the signal number. Up to 64 signals are kept until `drain` is
+/// called, further signals are dropped. Only one registry can exist at a
+/// time.
+pub struct SignalRegistry {
+    old: Vec<(SigNum, SigAction)>,
+}
+
+impl SignalRegistry {
+    /// Creates the registry, failing with `EBUSY` if another one exists.
+    pub fn new() -> Result<SignalRegistry> {
+        if REGISTRY_TAKEN.swap(true, Ordering::SeqCst) {
+            return Err(Error::Sys(Errno::EBUSY));
+        }
+
+        // Discard whatever a previous registry left behind
+        let queue = signal_queue();
+        while queue.pop().is_some() {}
+
+        Ok(SignalRegistry { old: Vec::new() })
+    }
+
+    /// Installs the handler for `signum`. The previous action is restored when
+    /// the registry is dropped.
+    pub fn add(&mut self, signum: SigNum) -> Result<()> {
+        let action = SigAction::new_siginfo(registry_trampoline, signal::SA_RESTART, SigSet::empty());
+        let old = try!(sigaction(signum, &action));
+
+        self.old.push((signum, old));
+        Ok(())
+    }
+
+    /// Returns the signals received since the last call, in order of
+    /// delivery.
+    pub fn drain(&mut self) -> Vec<SigNum> {
+        let queue = signal_queue();
+        let mut signals = Vec::new();
+
+        while let Some(signum) = queue.pop() {
+            signals.push(signum as SigNum);
+        }
+
+        signals
+    }
+}
+
+impl Drop for SignalRegistry {
+    fn drop(&mut self) {
+        for &(signum, ref old) in self.old.iter().rev() {
+            let _ = sigaction(signum, old);
+        }
+
+        REGISTRY_TAKEN.store(false, Ordering::SeqCst);
+    }
+}
+
+/*
+ *
+ * ===== Closure handlers =====
+ *
+ */
+
+/// A signal handler that captures state, see `register_handler`.
+pub type SignalHandler = Box<Fn(SigNum) + Send + Sync>;
+
+// Address of a leaked table with one slot per signal number, each holding
+// the address of a boxed `SignalHandler` or 0
+static HANDLER_TABLE: AtomicUsize = ATOMIC_USIZE_INIT;
+
+fn handler_table() -> &'static Vec<AtomicUsize> {
+    let mut ptr = HANDLER_TABLE.load(Ordering::SeqCst);
+
+    if ptr == 0 {
+        let table: Box<Vec<AtomicUsize>> =
+            Box::new((0..max_signal() + 1).map(|_| AtomicUsize::new(0)).collect());
+        let table_ptr = &*table as *const Vec<AtomicUsize> as usize;
+
+        ptr = HANDLER_TABLE.compare_and_swap(0, table_ptr, Ordering::SeqCst);
+
+        if ptr == 0 {
+            mem::forget(table);
+            ptr = table_ptr;
+        }
+    }
+
+    unsafe { &*(ptr as *const Vec<AtomicUsize>) }
+}
+
+extern fn handler_trampoline(signum: libc::c_int) {
+    let ptr = HANDLER_TABLE.load(Ordering::SeqCst);
+
+    if ptr == 0 {
+        return;
+    }
+
+    let table = unsafe { &*(ptr as *const Vec<AtomicUsize>) };
+
+    if let Some(slot) = table.get(signum as usize) {
+        let handler = slot.load(Ordering::SeqCst);
+
+        if handler != 0 {
+            let handler = unsafe { &*(handler as *const SignalHandler) };
+            handler(signum);
+        }
+    }
+}
+
+/// Installs a closure as the handler of `signum`. The previous action is
+/// restored when the returned token is dropped, but the closure itself is
+/// never freed, so it should not own anything large.
+///
+/// The closure runs in signal handler context, so it must itself be async
+/// signal safe: no allocation, no locks, only atomics and async signal safe
+/// calls. Only one closure can be registered per signal, a second
+/// registration fails with `EBUSY`, and an invalid signal number with
+/// `EINVAL`.
+pub fn register_handler(signum: SigNum, handler: SignalHandler) -> Result<HandlerToken> {
+    if signum <= 0 {
+        return Err(Error::Sys(Errno::EINVAL));
+    }
+
+    let slot = try!(handler_table().get(signum as usize).ok_or(Error::Sys(Errno::EINVAL)));
+    let handler: usize = unsafe { mem::transmute(Box::new(handler)) };
+
+    if slot.compare_and_swap(0, handler, Ordering::SeqCst) != 0 {
+        drop(unsafe { mem::transmute::<usize, Box<SignalHandler>>(handler) });
+        return Err(Error::Sys(Errno::EBUSY));
+    }
+
+    let action = SigAction::new(handler_trampoline, signal::SA_RESTART, SigSet::empty());
+
+    match sigaction(signum, &action) {
+        Ok(old) => Ok(HandlerToken { signum: signum, old: old }),
+        Err(e) => {
+            // Leaked like in `HandlerToken::drop`, the slot was visible to
+            // the trampoline already
+            slot.store(0, Ordering::SeqCst);
+            Err(e)
+        }
+    }
+}
+
+/// Keeps a closure registered with `register_handler` installed.
+pub struct HandlerToken {
+    signum: SigNum,
+    old: SigAction,
+}
+
+impl Drop for HandlerToken {
+    fn drop(&mut self) {
+        let _ = sigaction(self.signum, &self.old);
+
+        // The closure is leaked rather than freed: a trampoline still running
+        // on another thread may have loaded it before the slot was cleared,
+        // and there is no async signal safe way to wait for it to return.
+        handler_table()[self.signum as usize].store(0, Ordering::SeqCst);
+    }
+}
+
+/// How a `SigEvent` notifies the process once the event it describes has
+/// occurred.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SigevNotify {
+    /// No notification is delivered; completion has to be polled for.
+    SigevNone,
+    /// Deliver `signo`, with `value` available as the `si_value` of the
+    /// resulting siginfo.
+    SigevSignal { signo: SigNum, value: isize },
+}
+
+/// Describes how a timer or an asynchronous I/O request notifies the process,
+/// see `sigevent(7)`.
+#[derive(Clone, Copy)]
+pub struct SigEvent {
+    sigevent: sigevent
+}
+
+impl SigEvent {
+    pub fn new(notify: SigevNotify) -> SigEvent {
+        let mut s = unsafe { mem::zeroed::<sigevent>() };
+
+        match notify {
+            SigevNotify::SigevNone => {
+                s.sigev_notify = signal::SIGEV_NONE;
+            }
+            SigevNotify::SigevSignal { signo, value } => {
+                s.sigev_notify = signal::SIGEV_SIGNAL;
+                s.sigev_signo = signo;
+                s.sigev_value = value as *mut libc::c_void;
+            }
+        }
+
+        SigEvent { sigevent: s }
+    }
+
+    pub fn notify(&self) -> SigevNotify {
+        match self.sigevent.sigev_notify {
+            signal::SIGEV_SIGNAL => SigevNotify::SigevSignal {
+                signo: self.sigevent.sigev_signo,
+                value: self.sigevent.sigev_value as isize,
+            },
+            _ => SigevNotify::SigevNone,
+        }
+    }
+
+    /// The underlying `sigevent`, to be handed to `timer_create`, `aio_read`
+    /// and friends.
+    pub fn sigevent(&self) -> sigevent {
+        self.sigevent
+    }
+}
+
+#[test]
+pub fn test_struct_sizes() {
+    use nixtest;
+    nixtest::assert_size_of::<siginfo>("siginfo_t");
+    nixtest::assert_size_of::<sigevent>("sigevent");
+}
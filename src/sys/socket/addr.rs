@@ -1,8 +1,9 @@
 use {Result, Error, NixPath};
-use super::{consts, sa_family_t};
+use super::{consts, sa_family_t, sockaddr_storage};
 use errno::Errno;
 use libc;
-use std::{fmt, hash, mem, net, ptr};
+use std::{fmt, hash, mem, net, ptr, slice, str};
+use std::cmp::Ordering;
 use std::ffi::{CStr, OsStr};
 use std::path::Path;
 use std::os::unix::ffi::OsStrExt;
@@ -21,6 +22,27 @@ pub enum AddressFamily {
     Unix = consts::AF_UNIX,
     Inet = consts::AF_INET,
     Inet6 = consts::AF_INET6,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Netlink = consts::AF_NETLINK,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Vsock = consts::AF_VSOCK,
+}
+
+impl AddressFamily {
+    /// Converts a raw `AF_*` value, e.g. as returned by
+    /// `getsockopt(SO_DOMAIN)`, into an `AddressFamily`
+    pub fn from_i32(family: i32) -> Option<AddressFamily> {
+        match family {
+            consts::AF_UNIX => Some(AddressFamily::Unix),
+            consts::AF_INET => Some(AddressFamily::Inet),
+            consts::AF_INET6 => Some(AddressFamily::Inet6),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            consts::AF_NETLINK => Some(AddressFamily::Netlink),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            consts::AF_VSOCK => Some(AddressFamily::Vsock),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Copy)]
@@ -30,13 +52,24 @@ pub enum InetAddr {
 }
 
 impl InetAddr {
+    /// Converts from the std representation, keeping the flow info and scope
+    /// id of IPv6 addresses.
     pub fn from_std(std: &net::SocketAddr) -> InetAddr {
-        let ip = match *std {
-            net::SocketAddr::V4(ref addr) => IpAddr::V4(Ipv4Addr::from_std(&addr.ip())),
-            net::SocketAddr::V6(ref addr) => IpAddr::V6(Ipv6Addr::from_std(&addr.ip())),
-        };
+        match *std {
+            net::SocketAddr::V4(ref addr) => {
+                InetAddr::new(IpAddr::V4(Ipv4Addr::from_std(addr.ip())), addr.port())
+            }
+            net::SocketAddr::V6(ref addr) => {
+                let mut ret = InetAddr::new(IpAddr::V6(Ipv6Addr::from_std(addr.ip())), addr.port());
+
+                if let InetAddr::V6(ref mut sa) = ret {
+                    sa.sin6_flowinfo = addr.flowinfo();
+                    sa.sin6_scope_id = addr.scope_id();
+                }
 
-        InetAddr::new(ip, std.port())
+                ret
+            }
+        }
     }
 
     pub fn new(ip: IpAddr, port: u16) -> InetAddr {
@@ -59,6 +92,37 @@ impl InetAddr {
             }
         }
     }
+
+    /// `0.0.0.0:0`, e.g. as a buffer for the kernel to fill in.
+    pub fn zeroed_v4() -> InetAddr {
+        InetAddr::V4(libc::sockaddr_in {
+            sin_family: AddressFamily::Inet as sa_family_t,
+            .. unsafe { mem::zeroed() }
+        })
+    }
+
+    /// `[::]:0`, e.g. as a buffer for the kernel to fill in.
+    pub fn zeroed_v6() -> InetAddr {
+        InetAddr::V6(libc::sockaddr_in6 {
+            sin6_family: AddressFamily::Inet6 as sa_family_t,
+            .. unsafe { mem::zeroed() }
+        })
+    }
+
+    /// Builds an address from a literal IPv4 or IPv6 address, without
+    /// brackets, and a port. Host names are not resolved and fail with
+    /// `EINVAL`.
+    pub fn from_host_port(host: &str, port: u16) -> Result<InetAddr> {
+        if let Ok(ip) = host.parse::<net::Ipv4Addr>() {
+            return Ok(InetAddr::new(IpAddr::V4(Ipv4Addr::from_std(&ip)), port));
+        }
+
+        match host.parse::<net::Ipv6Addr>() {
+            Ok(ip) => Ok(InetAddr::new(IpAddr::V6(Ipv6Addr::from_std(&ip)), port)),
+            Err(_) => Err(Error::Sys(Errno::EINVAL)),
+        }
+    }
+
     /// Gets the IP address associated with this socket address.
     pub fn ip(&self) -> IpAddr {
         match *self {
@@ -67,6 +131,22 @@ impl InetAddr {
         }
     }
 
+    /// Gets the IPv4 address, if this is an IPv4 socket address.
+    pub fn as_ipv4(&self) -> Option<Ipv4Addr> {
+        match *self {
+            InetAddr::V4(ref sa) => Some(Ipv4Addr(sa.sin_addr)),
+            InetAddr::V6(..) => None,
+        }
+    }
+
+    /// Gets the IPv6 address, if this is an IPv6 socket address.
+    pub fn as_ipv6(&self) -> Option<Ipv6Addr> {
+        match *self {
+            InetAddr::V4(..) => None,
+            InetAddr::V6(ref sa) => Some(Ipv6Addr(sa.sin6_addr)),
+        }
+    }
+
     /// Gets the port number associated with this socket address
     pub fn port(&self) -> u16 {
         match *self {
@@ -93,8 +173,247 @@ impl InetAddr {
     pub fn to_str(&self) -> String {
         format!("{}", self)
     }
+
+    /// Compares only the IP address and the port. Unlike `==`, the flow info
+    /// and scope id of IPv6 addresses are ignored.
+    pub fn same_ip(&self, other: &InetAddr) -> bool {
+        match (*self, *other) {
+            (InetAddr::V4(..), InetAddr::V4(..)) => self == other,
+            (InetAddr::V6(ref a), InetAddr::V6(ref b)) => {
+                a.sin6_port == b.sin6_port &&
+                    a.sin6_addr.s6_addr == b.sin6_addr.s6_addr
+            }
+            _ => false,
+        }
+    }
+
+    /// The name to look up the PTR record of the address under, e.g.
+    /// `4.3.2.1.in-addr.arpa` for `1.2.3.4`, or the reversed nibbles under
+    /// `ip6.arpa` for an IPv6 address. No lookup is done.
+    pub fn reverse_dns_name(&self) -> String {
+        let bytes = self.to_bytes();
+        let mut name = String::new();
+
+        match *self {
+            InetAddr::V4(..) => {
+                for b in bytes.iter().rev() {
+                    name.push_str(&format!("{}.", b));
+                }
+
+                name.push_str("in-addr.arpa");
+            }
+            InetAddr::V6(..) => {
+                for b in bytes.iter().rev() {
+                    name.push_str(&format!("{:x}.{:x}.", b & 0xf, b >> 4));
+                }
+
+                name.push_str("ip6.arpa");
+            }
+        }
+
+        name
+    }
+
+    /// Compares only the IP address, ignoring the port as well as the flow
+    /// info and scope id of IPv6 addresses.
+    pub fn same_host(&self, other: &InetAddr) -> bool {
+        match (*self, *other) {
+            (InetAddr::V4(ref a), InetAddr::V4(ref b)) => {
+                a.sin_addr.s_addr == b.sin_addr.s_addr
+            }
+            (InetAddr::V6(ref a), InetAddr::V6(ref b)) => {
+                a.sin6_addr.s6_addr == b.sin6_addr.s6_addr
+            }
+            _ => false,
+        }
+    }
+
+    /// Maps an IPv4-mapped IPv6 address (`::ffff:a.b.c.d`) to the equivalent
+    /// IPv4 address, returning any other address unchanged. The two forms
+    /// otherwise compare and hash as different addresses.
+    pub fn canonicalize(&self) -> InetAddr {
+        match *self {
+            InetAddr::V6(ref sa) => {
+                let seg = Ipv6Addr(sa.sin6_addr).segments();
+
+                if &seg[..6] != &[0, 0, 0, 0, 0, 0xffff][..] {
+                    return *self;
+                }
+
+                let ip = Ipv4Addr::new((seg[6] >> 8) as u8, seg[6] as u8,
+                                       (seg[7] >> 8) as u8, seg[7] as u8);
+                InetAddr::new(IpAddr::V4(ip), self.port())
+            }
+            InetAddr::V4(..) => *self,
+        }
+    }
+
+    /// Copies the address into a `sockaddr_storage`, returning it along with
+    /// the length of the address.
+    pub fn to_storage(&self) -> (sockaddr_storage, libc::socklen_t) {
+        unsafe {
+            let mut storage: sockaddr_storage = mem::zeroed();
+
+            let len = match *self {
+                InetAddr::V4(ref sa) => {
+                    ptr::copy_nonoverlapping(sa, &mut storage as *mut sockaddr_storage as *mut libc::sockaddr_in, 1);
+                    mem::size_of::<libc::sockaddr_in>()
+                }
+                InetAddr::V6(ref sa) => {
+                    ptr::copy_nonoverlapping(sa, &mut storage as *mut sockaddr_storage as *mut libc::sockaddr_in6, 1);
+                    mem::size_of::<libc::sockaddr_in6>()
+                }
+            };
+
+            (storage, len as libc::socklen_t)
+        }
+    }
+
+    /// Reads the address held by a `sockaddr_storage`, `None` if it is not an
+    /// IPv4 or IPv6 address.
+    pub fn from_storage(storage: &sockaddr_storage) -> Option<InetAddr> {
+        unsafe {
+            match storage.ss_family as i32 {
+                consts::AF_INET => Some(InetAddr::V4(*(storage as *const sockaddr_storage as *const libc::sockaddr_in))),
+                consts::AF_INET6 => Some(InetAddr::V6(*(storage as *const sockaddr_storage as *const libc::sockaddr_in6))),
+                _ => None,
+            }
+        }
+    }
+
+    /// Returns true for `127.0.0.0/8` and `::1`
+    pub fn is_loopback(&self) -> bool {
+        match *self {
+            InetAddr::V4(ref sa) => Ipv4Addr(sa.sin_addr).octets()[0] == 127,
+            InetAddr::V6(ref sa) => Ipv6Addr(sa.sin6_addr).segments() == [0, 0, 0, 0, 0, 0, 0, 1],
+        }
+    }
+
+    /// Returns true for `224.0.0.0/4` and `ff00::/8`
+    pub fn is_multicast(&self) -> bool {
+        match *self {
+            InetAddr::V4(ref sa) => Ipv4Addr(sa.sin_addr).octets()[0] & 0xf0 == 224,
+            InetAddr::V6(ref sa) => Ipv6Addr(sa.sin6_addr).segments()[0] & 0xff00 == 0xff00,
+        }
+    }
+
+    /// Returns true for `0.0.0.0` and `::`
+    pub fn is_unspecified(&self) -> bool {
+        match *self {
+            InetAddr::V4(ref sa) => Ipv4Addr(sa.sin_addr).octets() == [0, 0, 0, 0],
+            InetAddr::V6(ref sa) => Ipv6Addr(sa.sin6_addr).segments() == [0; 8],
+        }
+    }
+
+    /// Returns true for `169.254.0.0/16` and `fe80::/10`
+    pub fn is_link_local(&self) -> bool {
+        match *self {
+            InetAddr::V4(ref sa) => {
+                let octets = Ipv4Addr(sa.sin_addr).octets();
+                octets[0] == 169 && octets[1] == 254
+            }
+            InetAddr::V6(ref sa) => Ipv6Addr(sa.sin6_addr).segments()[0] & 0xffc0 == 0xfe80,
+        }
+    }
+
+    /// Returns the address in network byte order: 4 bytes for IPv4 and 16
+    /// bytes for IPv6. The port is not included.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match *self {
+            InetAddr::V4(ref sa) => Ipv4Addr(sa.sin_addr).octets().to_vec(),
+            InetAddr::V6(ref sa) => {
+                let mut bytes = Vec::with_capacity(16);
+
+                for seg in Ipv6Addr(sa.sin6_addr).segments().iter() {
+                    bytes.push((*seg >> 8) as u8);
+                    bytes.push(*seg as u8);
+                }
+
+                bytes
+            }
+        }
+    }
+
+    /// Builds an address from its network byte order representation, as
+    /// returned by `to_bytes`. Fails with `EINVAL` unless `bytes` holds
+    /// exactly 4 or 16 bytes.
+    pub fn from_bytes(bytes: &[u8], port: u16) -> Result<InetAddr> {
+        match bytes.len() {
+            4 => {
+                let ip = IpAddr::new_v4(bytes[0], bytes[1], bytes[2], bytes[3]);
+                Ok(InetAddr::new(ip, port))
+            }
+            16 => {
+                let mut s = [0u16; 8];
+
+                for (i, seg) in s.iter_mut().enumerate() {
+                    *seg = ((bytes[2 * i] as u16) << 8) | bytes[2 * i + 1] as u16;
+                }
+
+                let ip = IpAddr::new_v6(s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7]);
+                Ok(InetAddr::new(ip, port))
+            }
+            _ => Err(Error::Sys(Errno::EINVAL)),
+        }
+    }
+
+    /// The broadcast address of the IPv4 network `addr` belongs to, i.e.
+    /// `addr | !netmask`.
+    pub fn broadcast_v4(addr: Ipv4Addr, netmask: Ipv4Addr) -> Ipv4Addr {
+        // Bitwise operations do not care about the byte order
+        Ipv4Addr(libc::in_addr { s_addr: addr.0.s_addr | !netmask.0.s_addr })
+    }
+
+    /// The address of the IPv4 network `addr` belongs to, i.e.
+    /// `addr & netmask`.
+    pub fn network_v4(addr: Ipv4Addr, netmask: Ipv4Addr) -> Ipv4Addr {
+        Ipv4Addr(libc::in_addr { s_addr: addr.0.s_addr & netmask.0.s_addr })
+    }
+
+    /// Encodes the address tagged with its family, as used by SOCKS5: a tag
+    /// byte, `1` for IPv4 or `4` for IPv6, the address bytes and the port,
+    /// all in network byte order.
+    pub fn to_tlv(&self) -> Vec<u8> {
+        let tag = match *self {
+            InetAddr::V4(..) => TLV_TAG_V4,
+            InetAddr::V6(..) => TLV_TAG_V6,
+        };
+
+        let port = self.port();
+        let mut bytes = vec![tag];
+        bytes.extend(self.to_bytes().into_iter());
+        bytes.push((port >> 8) as u8);
+        bytes.push(port as u8);
+        bytes
+    }
+
+    /// Decodes an address encoded by `to_tlv` from the start of `bytes`,
+    /// returning it along with the number of bytes used. Fails with `EINVAL`
+    /// on an unknown tag or when `bytes` is too short.
+    pub fn from_tlv(bytes: &[u8]) -> Result<(InetAddr, usize)> {
+        let len = match bytes.first() {
+            Some(&TLV_TAG_V4) => 4,
+            Some(&TLV_TAG_V6) => 16,
+            _ => return Err(Error::Sys(Errno::EINVAL)),
+        };
+
+        // Tag, address and port
+        let used = 1 + len + 2;
+
+        if bytes.len() < used {
+            return Err(Error::Sys(Errno::EINVAL));
+        }
+
+        let port = ((bytes[len + 1] as u16) << 8) | bytes[len + 2] as u16;
+        let addr = try!(InetAddr::from_bytes(&bytes[1..len + 1], port));
+
+        Ok((addr, used))
+    }
 }
 
+const TLV_TAG_V4: u8 = 1;
+const TLV_TAG_V6: u8 = 4;
+
 impl PartialEq for InetAddr {
     fn eq(&self, other: &InetAddr) -> bool {
         match (*self, *other) {
@@ -116,6 +435,35 @@ impl PartialEq for InetAddr {
 impl Eq for InetAddr {
 }
 
+/// Orders IPv4 before IPv6 addresses, then by the address in network byte
+/// order, then by port.
+impl PartialOrd for InetAddr {
+    fn partial_cmp(&self, other: &InetAddr) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for InetAddr {
+    fn cmp(&self, other: &InetAddr) -> Ordering {
+        match (*self, *other) {
+            (InetAddr::V4(ref a), InetAddr::V4(ref b)) => {
+                ( Ipv4Addr(a.sin_addr).octets(), u16::from_be(a.sin_port) ).cmp(
+                    &( Ipv4Addr(b.sin_addr).octets(), u16::from_be(b.sin_port) ))
+            }
+            (InetAddr::V6(ref a), InetAddr::V6(ref b)) => {
+                // Also compare the fields `PartialEq` looks at, to stay
+                // consistent with it
+                ( Ipv6Addr(a.sin6_addr).segments(), u16::from_be(a.sin6_port),
+                  a.sin6_flowinfo, a.sin6_scope_id ).cmp(
+                    &( Ipv6Addr(b.sin6_addr).segments(), u16::from_be(b.sin6_port),
+                       b.sin6_flowinfo, b.sin6_scope_id ))
+            }
+            (InetAddr::V4(..), InetAddr::V6(..)) => Ordering::Less,
+            (InetAddr::V6(..), InetAddr::V4(..)) => Ordering::Greater,
+        }
+    }
+}
+
 impl hash::Hash for InetAddr {
     fn hash<H: hash::Hasher>(&self, s: &mut H) {
         match *self {
@@ -145,11 +493,215 @@ impl fmt::Display for InetAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             InetAddr::V4(_) => write!(f, "{}:{}", self.ip(), self.port()),
-            InetAddr::V6(_) => write!(f, "[{}]:{}", self.ip(), self.port()),
+            InetAddr::V6(ref sa) => {
+                let ip = Ipv6Addr(sa.sin6_addr);
+
+                // A link-local address is only usable together with the
+                // interface it belongs to
+                if self.is_link_local() && sa.sin6_scope_id != 0 {
+                    write!(f, "[{}%{}]:{}", ip, sa.sin6_scope_id, self.port())
+                } else {
+                    write!(f, "[{}]:{}", ip, self.port())
+                }
+            }
         }
     }
 }
 
+/// Parses the format produced by `Display`, e.g. `127.0.0.1:80`,
+/// `[::1]:80` or `[fe80::1%2]:80`. Fails with `EINVAL`.
+impl str::FromStr for InetAddr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<InetAddr> {
+        let invalid = || Error::Sys(Errno::EINVAL);
+
+        if !s.starts_with('[') {
+            let addr = try!(s.parse::<net::SocketAddr>().map_err(|_| invalid()));
+            return Ok(InetAddr::from_std(&addr));
+        }
+
+        // std does not understand the `%scope_id` suffix, so take the
+        // address apart by hand
+        let end = try!(s.find(']').ok_or(invalid()));
+        let (host, rest) = (&s[1..end], &s[end + 1..]);
+
+        if !rest.starts_with(':') {
+            return Err(invalid());
+        }
+
+        let port = try!(rest[1..].parse::<u16>().map_err(|_| invalid()));
+
+        let (ip, scope_id) = match host.find('%') {
+            Some(i) => (&host[..i], try!(parse_scope_id(&host[i + 1..]))),
+            None => (host, 0),
+        };
+
+        let ip = try!(ip.parse::<net::Ipv6Addr>().map_err(|_| invalid()));
+        let mut addr = InetAddr::new(IpAddr::V6(Ipv6Addr::from_std(&ip)), port);
+
+        if let InetAddr::V6(ref mut sa) = addr {
+            sa.sin6_scope_id = scope_id;
+        }
+
+        Ok(addr)
+    }
+}
+
+// A scope is given either as an interface index or as an interface name,
+// e.g. `eth0`, which fails with `ENODEV` if there is no such interface
+fn parse_scope_id(scope: &str) -> Result<u32> {
+    if scope.is_empty() {
+        return Err(Error::Sys(Errno::EINVAL));
+    }
+
+    match scope.parse::<u32>() {
+        Ok(id) => Ok(id),
+        Err(_) => super::if_nametoindex(scope),
+    }
+}
+
+/*
+ *
+ * ===== IpNet =====
+ *
+ */
+
+/// A network in CIDR notation, e.g. `10.0.0.0/8`: the addresses sharing the
+/// first `prefix_len` bits with `addr`. Ports are ignored.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpNet {
+    addr: InetAddr,
+    prefix_len: u8,
+}
+
+impl IpNet {
+    /// Fails with `EINVAL` if `prefix_len` is larger than 32 for an IPv4
+    /// address or 128 for an IPv6 one.
+    pub fn new(addr: InetAddr, prefix_len: u8) -> Result<IpNet> {
+        let max = match addr {
+            InetAddr::V4(..) => 32,
+            InetAddr::V6(..) => 128,
+        };
+
+        if prefix_len > max {
+            return Err(Error::Sys(Errno::EINVAL));
+        }
+
+        Ok(IpNet { addr: addr, prefix_len: prefix_len })
+    }
+
+    pub fn addr(&self) -> InetAddr {
+        self.addr
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Whether `addr` is in the network. Always false for an address of the
+    /// other family.
+    pub fn contains(&self, addr: &InetAddr) -> bool {
+        let (net, other) = (self.addr.to_bytes(), addr.to_bytes());
+
+        if net.len() != other.len() {
+            return false;
+        }
+
+        let (bytes, bits) = ((self.prefix_len / 8) as usize, self.prefix_len % 8);
+
+        if net[..bytes] != other[..bytes] {
+            return false;
+        }
+
+        if bits == 0 {
+            return true;
+        }
+
+        let mask = !(0xffu8 >> bits);
+        net[bytes] & mask == other[bytes] & mask
+    }
+
+    /// Iterates over the host addresses of the network in ascending order,
+    /// all with the port of `addr`. The network and broadcast addresses of
+    /// an IPv4 network are skipped, except for /31 and /32 networks.
+    ///
+    /// The iterator is lazy, so large networks such as an IPv6 /64 can be
+    /// iterated over partially, but never collected.
+    pub fn hosts(&self) -> IpNetHosts {
+        let mut first = self.addr.to_bytes();
+        let mut last = first.clone();
+        let prefix_len = self.prefix_len as usize;
+
+        for (i, (f, l)) in first.iter_mut().zip(last.iter_mut()).enumerate() {
+            let mask = if prefix_len >= (i + 1) * 8 {
+                0xff
+            } else if prefix_len <= i * 8 {
+                0
+            } else {
+                !(0xffu8 >> (prefix_len - i * 8))
+            };
+
+            *f &= mask;
+            *l |= !mask;
+        }
+
+        if first.len() == 4 && prefix_len < 31 {
+            step_bytes(&mut first, true);
+            step_bytes(&mut last, false);
+        }
+
+        IpNetHosts { next: Some(first), last: last, port: self.addr.port() }
+    }
+}
+
+// Adds or subtracts one from a big endian number
+fn step_bytes(bytes: &mut [u8], up: bool) {
+    let (from, to) = if up { (0xff, 0) } else { (0, 0xff) };
+
+    for b in bytes.iter_mut().rev() {
+        if *b != from {
+            *b = if up { *b + 1 } else { *b - 1 };
+            return;
+        }
+
+        *b = to;
+    }
+}
+
+/// Iterator over the host addresses of an `IpNet`, see `IpNet::hosts`
+#[derive(Clone)]
+pub struct IpNetHosts {
+    next: Option<Vec<u8>>,
+    last: Vec<u8>,
+    port: u16,
+}
+
+impl Iterator for IpNetHosts {
+    type Item = InetAddr;
+
+    fn next(&mut self) -> Option<InetAddr> {
+        let cur = match self.next.take() {
+            Some(cur) => cur,
+            None => return None,
+        };
+
+        if cur != self.last {
+            let mut next = cur.clone();
+            step_bytes(&mut next, true);
+            self.next = Some(next);
+        }
+
+        Some(InetAddr::from_bytes(&cur, self.port).unwrap())
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr.ip(), self.prefix_len)
+    }
+}
+
 /*
  *
  * ===== IpAddr =====
@@ -329,8 +881,10 @@ impl fmt::Display for Ipv6Addr {
  *
  */
 
+/// A wrapper around `sockaddr_un`, together with the number of bytes of
+/// `sun_path` that are in use, see `path_len`.
 #[derive(Copy)]
-pub struct UnixAddr(pub libc::sockaddr_un);
+pub struct UnixAddr(pub libc::sockaddr_un, usize);
 
 impl UnixAddr {
     pub fn new<P: ?Sized + NixPath>(path: &P) -> Result<UnixAddr> {
@@ -343,33 +897,141 @@ impl UnixAddr {
                     .. mem::zeroed()
                 };
 
-                if bytes.len() >= ret.sun_path.len() {
-                    return Err(Error::Sys(Errno::ENAMETOOLONG));
-                }
+                try!(check_sun_path_len(&ret, bytes.len()));
 
                 ptr::copy(
                     bytes.as_ptr() as *const i8,
                     ret.sun_path.as_mut_ptr(),
                     bytes.len());
 
-                Ok(UnixAddr(ret))
+                Ok(UnixAddr(ret, bytes.len()))
             }
         }))
     }
 
+    /// Create an address in the Linux abstract namespace. The name is not
+    /// NUL terminated and may contain arbitrary bytes.
+    ///
+    /// [Further reading](http://man7.org/linux/man-pages/man7/unix.7.html)
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn new_abstract(name: &[u8]) -> Result<UnixAddr> {
+        unsafe {
+            let mut ret = libc::sockaddr_un {
+                sun_family: AddressFamily::Unix as sa_family_t,
+                .. mem::zeroed()
+            };
+
+            try!(check_sun_path_len(&ret, name.len()));
+
+            ptr::copy(
+                name.as_ptr() as *const i8,
+                ret.sun_path.as_mut_ptr().offset(1),
+                name.len());
+
+            Ok(UnixAddr(ret, name.len() + 1))
+        }
+    }
+
+    /// Create an unnamed address, holding only the address family. Binding a
+    /// socket to it autobinds the socket to a unique name in the abstract
+    /// namespace, which `getsockname` returns.
+    ///
+    /// [Further reading](http://man7.org/linux/man-pages/man7/unix.7.html)
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn new_unnamed() -> UnixAddr {
+        let ret = libc::sockaddr_un {
+            sun_family: AddressFamily::Unix as sa_family_t,
+            .. unsafe { mem::zeroed() }
+        };
+
+        UnixAddr(ret, 0)
+    }
+
+    /// Wraps a `sockaddr_un` of which the first `path_len` bytes of
+    /// `sun_path` are in use. Fails with `EINVAL` if `path_len` is larger
+    /// than `sun_path`.
+    pub fn from_raw(addr: libc::sockaddr_un, path_len: usize) -> Result<UnixAddr> {
+        if path_len > addr.sun_path.len() {
+            return Err(Error::Sys(Errno::EINVAL));
+        }
+
+        Ok(UnixAddr(addr, path_len))
+    }
+
+    /// The number of bytes of `sun_path` that are in use
+    pub fn path_len(&self) -> usize {
+        self.1
+    }
+
     pub fn path(&self) -> &Path {
         unsafe {
             let bytes = CStr::from_ptr(self.0.sun_path.as_ptr()).to_bytes();
             Path::new(<OsStr as OsStrExt>::from_bytes(bytes))
         }
     }
+
+    /// Returns the name of an abstract address, without the leading NUL, or
+    /// `None` if this is a pathname address.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn as_abstract(&self) -> Option<&[u8]> {
+        if self.is_abstract() {
+            Some(&self.sun_path()[1..])
+        } else {
+            None
+        }
+    }
+
+    fn is_abstract(&self) -> bool {
+        self.1 > 0 && self.0.sun_path[0] == 0
+    }
+
+    fn sun_path(&self) -> &[u8] {
+        unsafe { mem::transmute(&self.0.sun_path[..self.1]) }
+    }
+
+    // The bytes that identify the address. Pathnames end at the first NUL,
+    // while abstract names use every byte in use.
+    fn name_bytes(&self) -> &[u8] {
+        let path = self.sun_path();
+
+        if self.is_abstract() {
+            return path;
+        }
+
+        match path.iter().position(|&b| b == 0) {
+            Some(end) => &path[..end],
+            None => path,
+        }
+    }
+
+    /// The length of the address as passed to `bind` or `connect`
+    pub fn len(&self) -> libc::socklen_t {
+        (sun_path_offset() + self.1) as libc::socklen_t
+    }
+}
+
+// Fails with `ENAMETOOLONG` unless a name of `len` bytes fits in `sun_path`,
+// which is 108 bytes on Linux and 104 on the BSDs. A pathname needs room for
+// the terminating NUL, and an abstract name for the leading NUL marking it as
+// abstract, so either takes one byte more than its length.
+fn check_sun_path_len(addr: &libc::sockaddr_un, len: usize) -> Result<()> {
+    if len + 1 > addr.sun_path.len() {
+        return Err(Error::Sys(Errno::ENAMETOOLONG));
+    }
+
+    Ok(())
+}
+
+fn sun_path_offset() -> usize {
+    unsafe {
+        let addr: libc::sockaddr_un = mem::zeroed();
+        (&addr.sun_path as *const _ as usize) - (&addr as *const _ as usize)
+    }
 }
 
 impl PartialEq for UnixAddr {
     fn eq(&self, other: &UnixAddr) -> bool {
-        unsafe {
-            0 == libc::strcmp(self.0.sun_path.as_ptr(), other.0.sun_path.as_ptr())
-        }
+        self.name_bytes() == other.name_bytes()
     }
 }
 
@@ -378,7 +1040,7 @@ impl Eq for UnixAddr {
 
 impl hash::Hash for UnixAddr {
     fn hash<H: hash::Hasher>(&self, s: &mut H) {
-        ( self.0.sun_family, self.path() ).hash(s)
+        ( self.0.sun_family, self.name_bytes() ).hash(s)
     }
 }
 
@@ -390,7 +1052,119 @@ impl Clone for UnixAddr {
 
 impl fmt::Display for UnixAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.path().display().fmt(f)
+        if self.is_abstract() {
+            // Abstract names are conventionally shown with a leading `@`
+            write!(f, "@{}", String::from_utf8_lossy(&self.sun_path()[1..]))
+        } else {
+            self.path().display().fmt(f)
+        }
+    }
+}
+
+/*
+ *
+ * ===== NetlinkAddr =====
+ *
+ */
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct sockaddr_nl {
+    pub nl_family: sa_family_t,
+    nl_pad: libc::c_ushort,
+    pub nl_pid: u32,
+    pub nl_groups: u32,
+}
+
+/// The address of a netlink socket: the port id of the socket, usually the
+/// pid of the process owning it, and a bitmask of multicast groups.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man7/netlink.7.html)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NetlinkAddr(pub sockaddr_nl);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl NetlinkAddr {
+    /// Binding to a `pid` of 0 lets the kernel pick a unique port id.
+    pub fn new(pid: u32, groups: u32) -> NetlinkAddr {
+        NetlinkAddr(sockaddr_nl {
+            nl_family: AddressFamily::Netlink as sa_family_t,
+            nl_pad: 0,
+            nl_pid: pid,
+            nl_groups: groups,
+        })
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.0.nl_pid
+    }
+
+    pub fn groups(&self) -> u32 {
+        self.0.nl_groups
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl fmt::Display for NetlinkAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "pid: {} groups: {}", self.pid(), self.groups())
+    }
+}
+
+/*
+ *
+ * ===== VsockAddr =====
+ *
+ */
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct sockaddr_vm {
+    pub svm_family: sa_family_t,
+    svm_reserved1: libc::c_ushort,
+    pub svm_port: u32,
+    pub svm_cid: u32,
+    svm_zero: [u8; 4],
+}
+
+/// The address of a VM socket: a context id identifying the virtual machine
+/// or the host, and a port. `consts::VMADDR_CID_ANY` and
+/// `consts::VMADDR_PORT_ANY` are wildcards for binding,
+/// `consts::VMADDR_CID_HOST` addresses the host.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man7/vsock.7.html)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VsockAddr(pub sockaddr_vm);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl VsockAddr {
+    pub fn new(cid: u32, port: u32) -> VsockAddr {
+        VsockAddr(sockaddr_vm {
+            svm_family: AddressFamily::Vsock as sa_family_t,
+            svm_reserved1: 0,
+            svm_port: port,
+            svm_cid: cid,
+            svm_zero: [0; 4],
+        })
+    }
+
+    pub fn cid(&self) -> u32 {
+        self.0.svm_cid
+    }
+
+    pub fn port(&self) -> u32 {
+        self.0.svm_port
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl fmt::Display for VsockAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cid: {} port: {}", self.cid(), self.port())
     }
 }
 
@@ -404,10 +1178,35 @@ impl fmt::Display for UnixAddr {
 #[derive(Copy)]
 pub enum SockAddr {
     Inet(InetAddr),
-    Unix(UnixAddr)
+    Unix(UnixAddr),
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Netlink(NetlinkAddr),
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Vsock(VsockAddr),
 }
 
 impl SockAddr {
+    /// An all zero address of `family`, with only the family set, e.g. as a
+    /// buffer for the kernel to fill in. A Unix address has an empty path.
+    pub fn zeroed(family: AddressFamily) -> SockAddr {
+        match family {
+            AddressFamily::Inet => SockAddr::Inet(InetAddr::zeroed_v4()),
+            AddressFamily::Inet6 => SockAddr::Inet(InetAddr::zeroed_v6()),
+            AddressFamily::Unix => {
+                let addr = libc::sockaddr_un {
+                    sun_family: AddressFamily::Unix as sa_family_t,
+                    .. unsafe { mem::zeroed() }
+                };
+
+                SockAddr::Unix(UnixAddr(addr, 0))
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            AddressFamily::Netlink => SockAddr::new_netlink(0, 0),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            AddressFamily::Vsock => SockAddr::new_vsock(0, 0),
+        }
+    }
+
     pub fn new_inet(addr: InetAddr) -> SockAddr {
         SockAddr::Inet(addr)
     }
@@ -416,11 +1215,25 @@ impl SockAddr {
         Ok(SockAddr::Unix(try!(UnixAddr::new(path))))
     }
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn new_netlink(pid: u32, groups: u32) -> SockAddr {
+        SockAddr::Netlink(NetlinkAddr::new(pid, groups))
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn new_vsock(cid: u32, port: u32) -> SockAddr {
+        SockAddr::Vsock(VsockAddr::new(cid, port))
+    }
+
     pub fn family(&self) -> AddressFamily {
         match *self {
             SockAddr::Inet(InetAddr::V4(..)) => AddressFamily::Inet,
             SockAddr::Inet(InetAddr::V6(..)) => AddressFamily::Inet6,
             SockAddr::Unix(..) => AddressFamily::Unix,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            SockAddr::Netlink(..) => AddressFamily::Netlink,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            SockAddr::Vsock(..) => AddressFamily::Vsock,
         }
     }
 
@@ -428,13 +1241,94 @@ impl SockAddr {
         format!("{}", self)
     }
 
+    /// Converts an internet address to its `std` equivalent, `None` for the
+    /// other families, which `std::net` has no type for.
+    pub fn to_std(&self) -> Option<net::SocketAddr> {
+        match *self {
+            SockAddr::Inet(ref addr) => Some(addr.to_std()),
+            _ => None,
+        }
+    }
+
+    /// Decodes an address filled in by the kernel, where `len` is the number
+    /// of valid bytes behind `addr`. Returns `None` if the address is
+    /// truncated or of an unsupported family.
+    pub unsafe fn from_libc_sockaddr_len(addr: *const libc::sockaddr,
+                                         len: libc::socklen_t) -> Option<SockAddr> {
+        let len = len as usize;
+
+        // The family is followed by the path, so this is also the smallest
+        // length that includes the family
+        if addr.is_null() || len < sun_path_offset() {
+            return None;
+        }
+
+        match (*addr).sa_family as i32 {
+            consts::AF_INET if len >= mem::size_of::<libc::sockaddr_in>() => {
+                Some(SockAddr::Inet(InetAddr::V4(*(addr as *const libc::sockaddr_in))))
+            }
+            consts::AF_INET6 if len >= mem::size_of::<libc::sockaddr_in6>() => {
+                Some(SockAddr::Inet(InetAddr::V6(*(addr as *const libc::sockaddr_in6))))
+            }
+            consts::AF_UNIX if len <= mem::size_of::<libc::sockaddr_un>() => {
+                // Only `len` bytes are valid, the rest of the path stays zeroed
+                let mut ret: libc::sockaddr_un = mem::zeroed();
+                ptr::copy(addr as *const u8, &mut ret as *mut libc::sockaddr_un as *mut u8, len);
+                Some(SockAddr::Unix(UnixAddr(ret, len - sun_path_offset())))
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            consts::AF_NETLINK if len >= mem::size_of::<sockaddr_nl>() => {
+                Some(SockAddr::Netlink(NetlinkAddr(*(addr as *const sockaddr_nl))))
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            consts::AF_VSOCK if len >= mem::size_of::<sockaddr_vm>() => {
+                Some(SockAddr::Vsock(VsockAddr(*(addr as *const sockaddr_vm))))
+            }
+            _ => None,
+        }
+    }
+
+    /// The exact length of the address. For Unix addresses only the part of
+    /// `sun_path` in use is counted.
+    pub fn len(&self) -> libc::socklen_t {
+        match *self {
+            SockAddr::Inet(InetAddr::V4(..)) => mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+            SockAddr::Inet(InetAddr::V6(..)) => mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t,
+            SockAddr::Unix(ref addr) => addr.len(),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            SockAddr::Netlink(..) => mem::size_of::<sockaddr_nl>() as libc::socklen_t,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            SockAddr::Vsock(..) => mem::size_of::<sockaddr_vm>() as libc::socklen_t,
+        }
+    }
+
     pub unsafe fn as_ffi_pair(&self) -> (&libc::sockaddr, libc::socklen_t) {
         match *self {
-            SockAddr::Inet(InetAddr::V4(ref addr)) => (mem::transmute(addr), mem::size_of::<libc::sockaddr_in>() as libc::socklen_t),
-            SockAddr::Inet(InetAddr::V6(ref addr)) => (mem::transmute(addr), mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t),
-            SockAddr::Unix(UnixAddr(ref addr)) => (mem::transmute(addr), mem::size_of::<libc::sockaddr_un>() as libc::socklen_t),
+            SockAddr::Inet(InetAddr::V4(ref addr)) => (mem::transmute(addr), self.len()),
+            SockAddr::Inet(InetAddr::V6(ref addr)) => (mem::transmute(addr), self.len()),
+            SockAddr::Unix(UnixAddr(ref addr, _)) => (mem::transmute(addr), self.len()),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            SockAddr::Netlink(NetlinkAddr(ref addr)) => (mem::transmute(addr), self.len()),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            SockAddr::Vsock(VsockAddr(ref addr)) => (mem::transmute(addr), self.len()),
         }
     }
+
+    /// The raw bytes of the C struct of the address, the first `len()` of
+    /// them. The layout is platform specific, so the bytes only have a
+    /// meaning on the machine that produced them.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            let (ptr, len) = self.as_ffi_pair();
+            slice::from_raw_parts(ptr as *const libc::sockaddr as *const u8, len as usize)
+        }
+    }
+}
+
+impl AsRef<[u8]> for SockAddr {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
 }
 
 impl PartialEq for SockAddr {
@@ -446,6 +1340,14 @@ impl PartialEq for SockAddr {
             (SockAddr::Unix(ref a), SockAddr::Unix(ref b)) => {
                 a == b
             }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            (SockAddr::Netlink(ref a), SockAddr::Netlink(ref b)) => {
+                a == b
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            (SockAddr::Vsock(ref a), SockAddr::Vsock(ref b)) => {
+                a == b
+            }
             _ => false,
         }
     }
@@ -459,6 +1361,10 @@ impl hash::Hash for SockAddr {
         match *self {
             SockAddr::Inet(ref a) => a.hash(s),
             SockAddr::Unix(ref a) => a.hash(s),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            SockAddr::Netlink(ref a) => a.hash(s),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            SockAddr::Vsock(ref a) => a.hash(s),
         }
     }
 }
@@ -474,6 +1380,10 @@ impl fmt::Display for SockAddr {
         match *self {
             SockAddr::Inet(ref inet) => inet.fmt(f),
             SockAddr::Unix(ref unix) => unix.fmt(f),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            SockAddr::Netlink(ref nl) => nl.fmt(f),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            SockAddr::Vsock(ref vsock) => vsock.fmt(f),
         }
     }
 }
@@ -8,6 +8,8 @@ mod os {
     pub const AF_LOCAL: c_int = AF_UNIX;
     pub const AF_INET: c_int  = 2;
     pub const AF_INET6: c_int = 10;
+    pub const AF_NETLINK: c_int = 16;
+    pub const AF_VSOCK: c_int = 40;
 
     pub const SOCK_STREAM: c_int = 1;
     pub const SOCK_DGRAM: c_int = 2;
@@ -15,6 +17,10 @@ mod os {
     pub const SOCK_RAW: c_int = 3;
     pub const SOCK_RDM: c_int = 4;
 
+    pub const SHUT_RD: c_int = 0;
+    pub const SHUT_WR: c_int = 1;
+    pub const SHUT_RDWR: c_int = 2;
+
     pub const SOL_IP: c_int     = 0;
     pub const SOL_SOCKET: c_int = 1;
     pub const SOL_TCP: c_int    = 6;
@@ -24,6 +30,19 @@ mod os {
     pub const IPPROTO_IPV6: c_int = SOL_IPV6;
     pub const IPPROTO_TCP: c_int = SOL_TCP;
     pub const IPPROTO_UDP: c_int = SOL_UDP;
+    pub const IPPROTO_ICMP: c_int = 1;
+    pub const IPPROTO_ICMPV6: c_int = 58;
+    pub const IPPROTO_SCTP: c_int = 132;
+    pub const IPPROTO_RAW: c_int = 255;
+
+    // Netlink protocols, passed as the protocol of an `AF_NETLINK` socket
+    pub const NETLINK_ROUTE: c_int = 0;
+
+    // Special context ids and ports of `AF_VSOCK` addresses
+    pub const VMADDR_CID_ANY: u32 = 0xffffffff;
+    pub const VMADDR_CID_HYPERVISOR: u32 = 0;
+    pub const VMADDR_CID_HOST: u32 = 2;
+    pub const VMADDR_PORT_ANY: u32 = 0xffffffff;
 
     pub const SO_ACCEPTCONN: c_int = 30;
     pub const SO_BINDTODEVICE: c_int = 25;
@@ -57,12 +76,34 @@ mod os {
     pub const SO_TYPE: c_int = 3;
     pub const SO_BUSY_POLL: c_int = 46;
 
+    // Types of ancillary data, at the `SOL_SOCKET` level
+    pub const SCM_RIGHTS: c_int = 1;
+    pub const SCM_CREDENTIALS: c_int = 2;
+    pub const SCM_TIMESTAMP: c_int = SO_TIMESTAMP;
+
     // Socket options for TCP sockets
     pub const TCP_NODELAY: c_int = 1;
     pub const TCP_MAXSEG: c_int = 2;
     pub const TCP_CORK: c_int = 3;
+    pub const TCP_DEFER_ACCEPT: c_int = 9;
+    pub const TCP_INFO: c_int = 11;
+
+    // States of a TCP connection, as found in `tcp_info`
+    pub const TCP_ESTABLISHED: uint8_t = 1;
+    pub const TCP_SYN_SENT: uint8_t = 2;
+    pub const TCP_SYN_RECV: uint8_t = 3;
+    pub const TCP_FIN_WAIT1: uint8_t = 4;
+    pub const TCP_FIN_WAIT2: uint8_t = 5;
+    pub const TCP_TIME_WAIT: uint8_t = 6;
+    pub const TCP_CLOSE: uint8_t = 7;
+    pub const TCP_CLOSE_WAIT: uint8_t = 8;
+    pub const TCP_LAST_ACK: uint8_t = 9;
+    pub const TCP_LISTEN: uint8_t = 10;
+    pub const TCP_CLOSING: uint8_t = 11;
 
     // Socket options for the IP layer of the socket
+    pub const IP_TOS: c_int = 1;
+    pub const IP_TTL: c_int = 2;
     pub const IP_MULTICAST_IF: c_int = 32;
 
     pub type IpMulticastTtl = uint8_t;
@@ -71,6 +112,21 @@ mod os {
     pub const IP_MULTICAST_LOOP: c_int = 34;
     pub const IP_ADD_MEMBERSHIP: c_int = 35;
     pub const IP_DROP_MEMBERSHIP: c_int = 36;
+    pub const IP_PKTINFO: c_int = 8;
+    pub const IP_RECVERR: c_int = 11;
+
+    // Socket options for the IPv6 layer of the socket
+    pub const IPV6_UNICAST_HOPS: c_int = 16;
+    pub const IPV6_RECVERR: c_int = 25;
+    pub const IPV6_RECVPKTINFO: c_int = 49;
+    pub const IPV6_PKTINFO: c_int = 50;
+    pub const IPV6_TCLASS: c_int = 67;
+
+    // The origin of an error in the error queue, `ee_origin`
+    pub const SO_EE_ORIGIN_NONE: uint8_t = 0;
+    pub const SO_EE_ORIGIN_LOCAL: uint8_t = 1;
+    pub const SO_EE_ORIGIN_ICMP: uint8_t = 2;
+    pub const SO_EE_ORIGIN_ICMP6: uint8_t = 3;
 
     pub type InAddrT = u32;
 
@@ -83,7 +139,12 @@ mod os {
     // Flags for send/recv and their relatives
     pub const MSG_OOB: SockMessageFlags = 0x1;
     pub const MSG_PEEK: SockMessageFlags = 0x2;
+    pub const MSG_CTRUNC: SockMessageFlags = 0x8;
+    pub const MSG_TRUNC: SockMessageFlags = 0x20;
     pub const MSG_DONTWAIT: SockMessageFlags = 0x40;
+    pub const MSG_ERRQUEUE: SockMessageFlags = 0x2000;
+    pub const MSG_NOSIGNAL: SockMessageFlags = 0x4000;
+    pub const MSG_WAITFORONE: SockMessageFlags = 0x10000;
 }
 
 #[cfg(any(target_os = "macos", target_os = "ios"))]
@@ -101,11 +162,19 @@ mod os {
     pub const SOCK_RAW: c_int = 3;
     pub const SOCK_RDM: c_int = 4;
 
+    pub const SHUT_RD: c_int = 0;
+    pub const SHUT_WR: c_int = 1;
+    pub const SHUT_RDWR: c_int = 2;
+
     pub const SOL_SOCKET: c_int = 0xffff;
     pub const IPPROTO_IP: c_int = 0;
     pub const IPPROTO_IPV6: c_int = 41;
     pub const IPPROTO_TCP: c_int = 6;
     pub const IPPROTO_UDP: c_int = 17;
+    pub const IPPROTO_ICMP: c_int = 1;
+    pub const IPPROTO_ICMPV6: c_int = 58;
+    pub const IPPROTO_SCTP: c_int = 132;
+    pub const IPPROTO_RAW: c_int = 255;
 
     pub const SO_ACCEPTCONN: c_int          = 0x0002;
     pub const SO_BROADCAST: c_int           = 0x0020;
@@ -146,11 +215,17 @@ mod os {
     #[allow(overflowing_literals)]
     pub const SO_RESTRICT_DENYSET: c_int    = 0x80000000;
 
+    // Types of ancillary data, at the `SOL_SOCKET` level
+    pub const SCM_RIGHTS: c_int = 1;
+    pub const SCM_TIMESTAMP: c_int = 2;
+
     // Socket options for TCP sockets
     pub const TCP_NODELAY: c_int = 1;
     pub const TCP_MAXSEG: c_int = 2;
 
     // Socket options for the IP layer of the socket
+    pub const IP_TOS: c_int = 3;
+    pub const IP_TTL: c_int = 4;
     pub const IP_MULTICAST_IF: c_int = 9;
 
     pub type IpMulticastTtl = uint8_t;
@@ -159,6 +234,14 @@ mod os {
     pub const IP_MULTICAST_LOOP: c_int = 11;
     pub const IP_ADD_MEMBERSHIP: c_int = 12;
     pub const IP_DROP_MEMBERSHIP: c_int = 13;
+    // Both enables the control message and is its type
+    pub const IP_PKTINFO: c_int = 26;
+
+    // Socket options for the IPv6 layer of the socket
+    pub const IPV6_UNICAST_HOPS: c_int = 4;
+    pub const IPV6_RECVPKTINFO: c_int = 61;
+    pub const IPV6_PKTINFO: c_int = 46;
+    pub const IPV6_TCLASS: c_int = 36;
 
     pub type InAddrT = u32;
 
@@ -171,5 +254,7 @@ mod os {
     // Flags for send/recv and their relatives
     pub const MSG_OOB: SockMessageFlags = 0x1;
     pub const MSG_PEEK: SockMessageFlags = 0x2;
+    pub const MSG_TRUNC: SockMessageFlags = 0x10;
+    pub const MSG_CTRUNC: SockMessageFlags = 0x20;
     pub const MSG_DONTWAIT: SockMessageFlags = 0x80;
 }
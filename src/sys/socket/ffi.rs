@@ -1,5 +1,7 @@
-use libc::{c_int, c_void, socklen_t};
-pub use libc::{socket, listen, bind, accept, connect, setsockopt, sendto, recvfrom, getsockname, getpeername};
+use libc::{c_char, c_int, c_uint, c_void, size_t, socklen_t, ssize_t};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use sys::time::TimeSpec;
+pub use libc::{socket, listen, bind, accept, connect, setsockopt, send, sendto, recv, recvfrom, getsockname, getpeername};
 
 extern {
     pub fn getsockopt(
@@ -8,4 +10,63 @@ extern {
         optname: c_int,
         optval: *mut c_void,
         optlen: *mut socklen_t) -> c_int;
+
+    pub fn recvmsg(sockfd: c_int, msg: *mut msghdr, flags: c_int) -> ssize_t;
+
+    pub fn sendmsg(sockfd: c_int, msg: *const msghdr, flags: c_int) -> ssize_t;
+
+    pub fn socketpair(
+        domain: c_int,
+        ty: c_int,
+        protocol: c_int,
+        sv: *mut c_int) -> c_int;
+
+    pub fn shutdown(sockfd: c_int, how: c_int) -> c_int;
+
+    pub fn if_nametoindex(ifname: *const c_char) -> c_uint;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn recvmmsg(sockfd: c_int, msgvec: *mut mmsghdr, vlen: c_uint, flags: c_int,
+                    timeout: *mut TimeSpec) -> c_int;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn sendmmsg(sockfd: c_int, msgvec: *mut mmsghdr, vlen: c_uint, flags: c_int) -> c_int;
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub type iovlen_t = size_t;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub type controllen_t = size_t;
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub type iovlen_t = c_int;
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub type controllen_t = socklen_t;
+
+#[repr(C)]
+pub struct msghdr {
+    pub msg_name: *mut c_void,
+    pub msg_namelen: socklen_t,
+    // points to an array of `IoVec`
+    pub msg_iov: *mut c_void,
+    pub msg_iovlen: iovlen_t,
+    pub msg_control: *mut c_void,
+    pub msg_controllen: controllen_t,
+    pub msg_flags: c_int,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[repr(C)]
+pub struct mmsghdr {
+    pub msg_hdr: msghdr,
+    pub msg_len: c_uint,
+}
+
+#[repr(C)]
+pub struct cmsghdr {
+    pub cmsg_len: controllen_t,
+    pub cmsg_level: c_int,
+    pub cmsg_type: c_int,
 }
@@ -4,10 +4,17 @@
 use {Error, Result, from_ffi};
 use errno::Errno;
 use features;
-use fcntl::{fcntl, Fd, FD_CLOEXEC, O_NONBLOCK};
-use fcntl::FcntlArg::{F_SETFD, F_SETFL};
-use libc::{c_void, c_int, socklen_t, size_t};
-use std::{fmt, mem, ptr};
+use fcntl::{fcntl, Fd, OFlag, FD_CLOEXEC, O_NONBLOCK};
+use fcntl::FcntlArg::{F_GETFL, F_SETFD, F_SETFL};
+use poll::{poll, PollFd, POLLOUT};
+use sys::time::{TimeSpec, TimeVal};
+use sys::uio::IoVec;
+use unistd::OwnedFd;
+use libc::{c_void, c_int, c_uint, socklen_t, size_t};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use libc::{pid_t, uid_t, gid_t};
+use std::{cmp, fmt, io, iter, mem, net, ptr, slice};
+use std::ffi::CString;
 
 mod addr;
 mod consts;
@@ -27,9 +34,18 @@ pub use self::addr::{
     InetAddr,
     UnixAddr,
     IpAddr,
+    IpNet,
+    IpNetHosts,
     Ipv4Addr,
     Ipv6Addr,
 };
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::addr::{
+    NetlinkAddr,
+    VsockAddr,
+    sockaddr_nl,
+    sockaddr_vm,
+};
 pub use libc::{
     in_addr,
     in6_addr,
@@ -66,6 +82,21 @@ pub enum SockType {
     Rdm = consts::SOCK_RDM,
 }
 
+impl SockType {
+    /// Converts a raw `SOCK_*` value, e.g. as returned by
+    /// `getsockopt(SO_TYPE)`, into a `SockType`
+    pub fn from_i32(ty: i32) -> Option<SockType> {
+        match ty {
+            consts::SOCK_STREAM => Some(SockType::Stream),
+            consts::SOCK_DGRAM => Some(SockType::Datagram),
+            consts::SOCK_SEQPACKET => Some(SockType::SeqPacket),
+            consts::SOCK_RAW => Some(SockType::Raw),
+            consts::SOCK_RDM => Some(SockType::Rdm),
+            _ => None,
+        }
+    }
+}
+
 // Extra flags - Supported by Linux 2.6.27, normalized on other platforms
 bitflags!(
     flags SockFlag: c_int {
@@ -76,8 +107,41 @@ bitflags!(
 
 /// Create an endpoint for communication
 ///
+/// The socket is created with protocol 0, the default for the domain. For
+/// `AddressFamily::Netlink` this is `NETLINK_ROUTE`.
+///
 /// [Further reading](http://man7.org/linux/man-pages/man2/socket.2.html)
 pub fn socket(domain: AddressFamily, ty: SockType, flags: SockFlag) -> Result<Fd> {
+    socket_protocol(domain, ty, flags, 0)
+}
+
+/// Internet protocols, as passed to `socket_with_proto`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(i32)]
+pub enum IpProto {
+    Tcp = IPPROTO_TCP,
+    Udp = IPPROTO_UDP,
+    Icmp = IPPROTO_ICMP,
+    IcmpV6 = IPPROTO_ICMPV6,
+    /// Raw IP packets, for sending only
+    Raw = IPPROTO_RAW,
+    Sctp = IPPROTO_SCTP,
+}
+
+impl IpProto {
+    /// The `IPPROTO_*` number of the protocol
+    pub fn as_c_int(&self) -> c_int {
+        *self as c_int
+    }
+}
+
+/// Like `socket`, but with an explicit protocol, e.g. `IpProto::Icmp` for a
+/// raw ICMP socket, which needs `CAP_NET_RAW`.
+pub fn socket_with_proto(domain: AddressFamily, ty: SockType, flags: SockFlag, proto: IpProto) -> Result<Fd> {
+    socket_protocol(domain, ty, flags, proto.as_c_int())
+}
+
+fn socket_protocol(domain: AddressFamily, ty: SockType, flags: SockFlag, protocol: c_int) -> Result<Fd> {
     let mut ty = ty as c_int;
     let feat_atomic = features::socket_atomic_cloexec();
 
@@ -86,23 +150,79 @@ pub fn socket(domain: AddressFamily, ty: SockType, flags: SockFlag) -> Result<Fd
     }
 
     // TODO: Check the kernel version
-    let res = unsafe { ffi::socket(domain as c_int, ty, 0) };
+    let res = unsafe { ffi::socket(domain as c_int, ty, protocol) };
 
     if res < 0 {
         return Err(Error::Sys(Errno::last()));
     }
 
+    // Closes the socket if setting the flags fails
+    let fd = OwnedFd::new(res);
+
     if !feat_atomic {
-        if flags.contains(SOCK_CLOEXEC) {
-            try!(fcntl(res, F_SETFD(FD_CLOEXEC)));
-        }
+        try!(set_sock_flags(fd.as_raw_fd(), flags));
+    }
 
-        if flags.contains(SOCK_NONBLOCK) {
-            try!(fcntl(res, F_SETFL(O_NONBLOCK)));
-        }
+    Ok(fd.into_raw_fd())
+}
+
+// Emulates SOCK_CLOEXEC and SOCK_NONBLOCK where they are not supported
+fn set_sock_flags(fd: Fd, flags: SockFlag) -> Result<()> {
+    if flags.contains(SOCK_CLOEXEC) {
+        try!(fcntl(fd, F_SETFD(FD_CLOEXEC)));
     }
 
-    Ok(res)
+    if flags.contains(SOCK_NONBLOCK) {
+        try!(fcntl(fd, F_SETFL(O_NONBLOCK)));
+    }
+
+    Ok(())
+}
+
+/// Create a pair of connected sockets
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/socketpair.2.html)
+pub fn socketpair(domain: AddressFamily, ty: SockType, flags: SockFlag) -> Result<(Fd, Fd)> {
+    let mut ty = ty as c_int;
+    let feat_atomic = features::socket_atomic_cloexec();
+
+    if feat_atomic {
+        ty = ty | flags.bits();
+    }
+
+    let mut fds = [-1, -1];
+    let res = unsafe { ffi::socketpair(domain as c_int, ty, 0, fds.as_mut_ptr()) };
+
+    if res < 0 {
+        return Err(Error::Sys(Errno::last()));
+    }
+
+    // Closes both sockets if setting the flags fails
+    let (a, b) = (OwnedFd::new(fds[0]), OwnedFd::new(fds[1]));
+
+    if !feat_atomic {
+        try!(set_sock_flags(a.as_raw_fd(), flags));
+        try!(set_sock_flags(b.as_raw_fd(), flags));
+    }
+
+    Ok((a.into_raw_fd(), b.into_raw_fd()))
+}
+
+/// Returns the index of the network interface named `name`, e.g. to use as
+/// the scope id of a link-local IPv6 address. Fails with `ENODEV` if there is
+/// no such interface.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man3/if_nametoindex.3.html)
+pub fn if_nametoindex(name: &str) -> Result<u32> {
+    let name = try!(CString::new(name).map_err(|_| Error::Sys(Errno::ENODEV)));
+    let res = unsafe { ffi::if_nametoindex(name.as_ptr()) };
+
+    // errno is not set consistently across platforms
+    if res == 0 {
+        return Err(Error::Sys(Errno::ENODEV));
+    }
+
+    Ok(res as u32)
 }
 
 /// Listen for connections on a socket
@@ -113,6 +233,25 @@ pub fn listen(sockfd: Fd, backlog: usize) -> Result<()> {
     from_ffi(res)
 }
 
+/// Which directions of a connection `shutdown` closes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(i32)]
+pub enum Shutdown {
+    /// Further receptions are disallowed
+    Read = consts::SHUT_RD,
+    /// Further transmissions are disallowed, the peer reads end of file
+    Write = consts::SHUT_WR,
+    Both = consts::SHUT_RDWR,
+}
+
+/// Shut down part or all of a full-duplex connection
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/shutdown.2.html)
+pub fn shutdown(fd: Fd, how: Shutdown) -> Result<()> {
+    let res = unsafe { ffi::shutdown(fd, how as c_int) };
+    from_ffi(res)
+}
+
 /// Bind a name to a socket
 ///
 /// [Further reading](http://man7.org/linux/man-pages/man2/bind.2.html)
@@ -125,6 +264,26 @@ pub fn bind(fd: Fd, addr: &SockAddr) -> Result<()> {
     from_ffi(res)
 }
 
+/// Like `bind`, but taking a `std::net::SocketAddr`
+pub fn bind_std(fd: Fd, addr: net::SocketAddr) -> Result<()> {
+    bind(fd, &SockAddr::Inet(InetAddr::from_std(&addr)))
+}
+
+/// Like `bind`, but first checks that `addr` belongs to the domain the
+/// socket was created with. A mismatch fails with `EAFNOSUPPORT` rather than
+/// the `EINVAL` the kernel returns for e.g. an IPv6 address on an IPv4
+/// socket.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn bind_checked(fd: Fd, addr: &SockAddr) -> Result<()> {
+    let domain = try!(getsockopt(fd, SockLevel::Socket, sockopt::SocketDomain));
+
+    if domain != Some(addr.family()) {
+        return Err(Error::Sys(Errno::EAFNOSUPPORT));
+    }
+
+    bind(fd, addr)
+}
+
 /// Accept a connection on a socket
 ///
 /// [Further reading](http://man7.org/linux/man-pages/man2/accept.2.html)
@@ -153,19 +312,84 @@ fn accept4_polyfill(sockfd: Fd, flags: SockFlag) -> Result<Fd> {
         return Err(Error::Sys(Errno::last()));
     }
 
-    if flags.contains(SOCK_CLOEXEC) {
-        try!(fcntl(res, F_SETFD(FD_CLOEXEC)));
-    }
+    // Closes the connection if setting the flags fails
+    let fd = OwnedFd::new(res);
+    try!(set_sock_flags(fd.as_raw_fd(), flags));
 
-    if flags.contains(SOCK_NONBLOCK) {
-        try!(fcntl(res, F_SETFL(O_NONBLOCK)));
+    Ok(fd.into_raw_fd())
+}
+
+// Accepts a connection, also returning the address of the peer
+fn accept_from(sockfd: Fd) -> Result<(Fd, SockAddr)> {
+    accept_with(sockfd, sockaddr_storage_to_addr)
+}
+
+// Like `accept_from`, decoding the address with `to_addr`. The connection is
+// closed if that fails.
+fn accept_with(sockfd: Fd, to_addr: unsafe fn(&sockaddr_storage, usize) -> Result<SockAddr>) -> Result<(Fd, SockAddr)> {
+    unsafe {
+        let mut addr: sockaddr_storage = mem::zeroed();
+        let mut len = mem::size_of::<sockaddr_storage>() as socklen_t;
+
+        let res = ffi::accept(sockfd, &mut addr as *mut sockaddr_storage as *mut sockaddr, &mut len);
+
+        if res < 0 {
+            return Err(Error::last());
+        }
+
+        let fd = OwnedFd::new(res);
+        let addr = try!(to_addr(&addr, len as usize));
+
+        Ok((fd.into_raw_fd(), addr))
     }
+}
 
-    Ok(res)
+/// An iterator over the connections accepted on a listening socket, see
+/// `incoming`.
+pub struct Incoming {
+    fd: Fd,
+    done: bool,
+}
+
+/// Returns an iterator accepting connections on `fd`, which must be a
+/// listening socket. Each item is the new connection and the address of the
+/// peer.
+///
+/// `EINTR` and `ECONNABORTED`, a connection reset before it was accepted,
+/// are retried. Any other error is returned and ends the iteration.
+pub fn incoming(fd: Fd) -> Incoming {
+    Incoming { fd: fd, done: false }
+}
+
+impl Iterator for Incoming {
+    type Item = Result<(Fd, SockAddr)>;
+
+    fn next(&mut self) -> Option<Result<(Fd, SockAddr)>> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            match accept_from(self.fd) {
+                Err(Error::Sys(Errno::EINTR)) |
+                Err(Error::Sys(Errno::ECONNABORTED)) => continue,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+                Ok(conn) => return Some(Ok(conn)),
+            }
+        }
+    }
 }
 
 /// Initiate a connection on a socket
 ///
+/// A blocking connect interrupted by a signal keeps establishing the
+/// connection in the background, and calling `connect` again would fail with
+/// `EALREADY`. Instead, the `EINTR` is handled by waiting for the socket to
+/// become writable and returning the outcome from `SO_ERROR`.
+///
 /// [Further reading](http://man7.org/linux/man-pages/man2/connect.2.html)
 pub fn connect(fd: Fd, addr: &SockAddr) -> Result<()> {
     let res = unsafe {
@@ -173,7 +397,72 @@ pub fn connect(fd: Fd, addr: &SockAddr) -> Result<()> {
         ffi::connect(fd, ptr, len)
     };
 
-    from_ffi(res)
+    match from_ffi(res) {
+        Err(Error::Sys(Errno::EINTR)) => connect_complete(fd, -1),
+        res => res,
+    }
+}
+
+/// Like `connect`, but taking a `std::net::SocketAddr`
+pub fn connect_std(fd: Fd, addr: net::SocketAddr) -> Result<()> {
+    connect(fd, &SockAddr::Inet(InetAddr::from_std(&addr)))
+}
+
+/// Initiate a connection on a socket, giving up with `ETIMEDOUT` if it has not
+/// been established within `timeout`.
+///
+/// The socket is switched to non-blocking mode for the duration of the call
+/// and restored afterwards. A signal arriving while waiting fails the call
+/// with `EINTR`; the connection attempt carries on, so the caller can wait
+/// for it by polling the socket for writability.
+pub fn connect_timeout(fd: Fd, addr: &SockAddr, timeout: TimeSpec) -> Result<()> {
+    let flags = OFlag::from_bits_truncate(try!(fcntl(fd, F_GETFL)));
+
+    if flags.contains(O_NONBLOCK) {
+        return connect_wait(fd, addr, timeout);
+    }
+
+    try!(fcntl(fd, F_SETFL(flags | O_NONBLOCK)));
+
+    let res = connect_wait(fd, addr, timeout);
+    let restored = fcntl(fd, F_SETFL(flags));
+
+    res.and(restored.map(|_| ()))
+}
+
+fn connect_wait(fd: Fd, addr: &SockAddr, timeout: TimeSpec) -> Result<()> {
+    match connect(fd, addr) {
+        Err(Error::Sys(Errno::EINPROGRESS)) => {}
+        res => return res,
+    }
+
+    // Rounded up, so that a timeout below a millisecond still waits. Huge
+    // timeouts saturate instead of overflowing, negative ones wait forever.
+    let millis = (timeout.tv_sec as i64).checked_mul(1000)
+        .and_then(|ms| ms.checked_add((timeout.tv_nsec as i64 + 999_999) / 1_000_000))
+        .unwrap_or(i64::max_value());
+
+    connect_complete(fd, cmp::max(-1, cmp::min(millis, c_int::max_value() as i64)) as c_int)
+}
+
+// Waits for a connection in progress and returns its outcome. Without a
+// timeout, i.e. a negative one, interrupted waits are resumed.
+fn connect_complete(fd: Fd, timeout: c_int) -> Result<()> {
+    let mut fds = [PollFd::new(fd, POLLOUT)];
+
+    loop {
+        match poll(&mut fds, timeout) {
+            Ok(0) => return Err(Error::Sys(Errno::ETIMEDOUT)),
+            Ok(_) => break,
+            Err(Error::Sys(Errno::EINTR)) if timeout < 0 => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    match try!(getsockopt(fd, SockLevel::Socket, sockopt::SocketError)) {
+        0 => Ok(()),
+        err => Err(Error::Sys(Errno::from_i32(err))),
+    }
 }
 
 /// Receive data from a connectionless or connection-oriented socket. Returns
@@ -202,6 +491,745 @@ pub fn recvfrom(sockfd: Fd, buf: &mut [u8]) -> Result<(usize, SockAddr)> {
     }
 }
 
+/// Like `recvfrom`, but stores the address of the sender in `storage`
+/// instead of decoding it, so one storage can be reused across calls without
+/// being reinitialized. Decode it with e.g. `InetAddr::from_storage`.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/recvfrom.2.html)
+pub fn recvfrom_into(fd: Fd, buf: &mut [u8], storage: &mut sockaddr_storage) -> Result<usize> {
+    let mut len = mem::size_of::<sockaddr_storage>() as socklen_t;
+
+    let ret = unsafe {
+        ffi::recvfrom(
+            fd,
+            buf.as_mut_ptr() as *mut c_void,
+            buf.len() as size_t,
+            0,
+            storage as *mut sockaddr_storage as *mut sockaddr,
+            &mut len as *mut socklen_t)
+    };
+
+    if ret < 0 {
+        return Err(Error::last());
+    }
+
+    Ok(ret as usize)
+}
+
+/// Like `recv`, but also returns the address of the sender when the kernel
+/// reports one, e.g. to check it against the peer of a connected UDP socket.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/recvmsg.2.html)
+pub fn recv_with_addr(fd: Fd, buf: &mut [u8], flags: SockMessageFlags) -> Result<(usize, Option<SockAddr>)> {
+    let msg = try!(recvmsg::<()>(fd, &mut [IoVec::from_mut_slice(buf)], None, flags));
+
+    Ok((msg.bytes, msg.address))
+}
+
+/// Like `recvfrom`, but passes `flags` down and also returns the flags of
+/// the received message, e.g. `MSG_TRUNC` when a datagram did not fit in
+/// `buf`. On Linux, passing `MSG_TRUNC` in `flags` makes the returned length
+/// the real size of the datagram, even when that is larger than `buf`.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/recvmsg.2.html)
+pub fn recvfrom_flags(fd: Fd, buf: &mut [u8], flags: SockMessageFlags) -> Result<(usize, SockAddr, SockMessageFlags)> {
+    let msg = try!(recvmsg::<()>(fd, &mut [IoVec::from_mut_slice(buf)], None, flags));
+
+    // Connected sockets may not report the sender
+    let addr = match msg.address {
+        Some(addr) => addr,
+        None => try!(getpeername(fd)),
+    };
+
+    Ok((msg.bytes, addr, msg.flags))
+}
+
+/// Send data on a connected socket. Returns the number of bytes sent.
+///
+/// With `MSG_OOB` on a TCP socket only the last byte of `buf` is sent as
+/// urgent data; send one byte at a time.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/send.2.html)
+pub fn send(fd: Fd, buf: &[u8], flags: SockMessageFlags) -> Result<usize> {
+    let ret = unsafe {
+        ffi::send(fd, buf.as_ptr() as *const c_void, buf.len() as size_t, flags)
+    };
+
+    if ret < 0 {
+        Err(Error::Sys(Errno::last()))
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+/// Receive data from a connected socket. Returns the number of bytes
+/// received, 0 once a stream socket's peer has shut down.
+///
+/// For `SockType::SeqPacket` and `SockType::Datagram` sockets each call
+/// returns exactly one message. If it does not fit in `buf` the rest of it is
+/// discarded.
+///
+/// With `MSG_OOB` a single byte of urgent data is received, unless
+/// `sockopt::OobInline` is set, in which case it is delivered in the normal
+/// data stream instead.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/recv.2.html)
+pub fn recv(fd: Fd, buf: &mut [u8], flags: SockMessageFlags) -> Result<usize> {
+    let ret = unsafe {
+        ffi::recv(fd, buf.as_mut_ptr() as *mut c_void, buf.len() as size_t, flags)
+    };
+
+    if ret < 0 {
+        Err(Error::Sys(Errno::last()))
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+/// Adapts a socket to `std::io::Read` and `std::io::Write`, implemented with
+/// `recv` and `send`.
+///
+/// The caller keeps ownership of the descriptor, it is not closed when the
+/// `Socket` is dropped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Socket(pub Fd);
+
+fn to_io_error(err: Error) -> io::Error {
+    io::Error::from_raw_os_error(err.errno() as i32)
+}
+
+/// Reads return `Ok(0)` once the peer has shut down its side of the
+/// connection, and an error of kind `ConnectionReset` if it was reset.
+/// Interrupted calls are retried.
+impl io::Read for Socket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match recv(self.0, buf, 0) {
+                Err(Error::Sys(Errno::EINTR)) => {}
+                res => return res.map_err(to_io_error),
+            }
+        }
+    }
+}
+
+impl io::Write for Socket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        loop {
+            match send(self.0, buf, 0) {
+                Err(Error::Sys(Errno::EINTR)) => {}
+                res => return res.map_err(to_io_error),
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // Sockets are not buffered in userspace
+        Ok(())
+    }
+}
+
+/// Like `send`, but a peer that has gone away results in `EPIPE` rather than
+/// a `SIGPIPE`.
+///
+/// On Linux this adds `MSG_NOSIGNAL` to `flags`. Elsewhere `SO_NOSIGPIPE` is
+/// enabled on the socket, which stays in effect for later writes too.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn send_nosignal(fd: Fd, buf: &[u8], flags: SockMessageFlags) -> Result<usize> {
+    send(fd, buf, flags | MSG_NOSIGNAL)
+}
+
+/// Like `send`, but a peer that has gone away results in `EPIPE` rather than
+/// a `SIGPIPE`.
+///
+/// On Linux this adds `MSG_NOSIGNAL` to `flags`. Elsewhere `SO_NOSIGPIPE` is
+/// enabled on the socket, which stays in effect for later writes too.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub fn send_nosignal(fd: Fd, buf: &[u8], flags: SockMessageFlags) -> Result<usize> {
+    try!(setsockopt(fd, SockLevel::Socket, sockopt::NoSigPipe, true));
+    send(fd, buf, flags)
+}
+
+/// Returns `len` rounded up to the alignment of control message headers,
+/// like `CMSG_ALIGN`
+fn cmsg_align(len: usize) -> usize {
+    let align = mem::align_of::<ffi::cmsghdr>();
+    (len + align - 1) & !(align - 1)
+}
+
+/// The offset of the data from the start of a control message, like
+/// `CMSG_LEN(0)`
+fn cmsg_data_offset() -> usize {
+    cmsg_align(mem::size_of::<ffi::cmsghdr>())
+}
+
+/// A buffer for receiving a control message carrying a `T`, suitably
+/// aligned and sized to be passed to `recvmsg`, e.g. `CmsgSpace<[Fd; 2]>`.
+///
+/// Room for several messages is made by nesting, e.g.
+/// `CmsgSpace<([Fd; 1], CmsgSpace<ucred>)>` for a descriptor along with
+/// credentials.
+pub struct CmsgSpace<T> {
+    _hdr: ffi::cmsghdr,
+    _data: T,
+}
+
+impl<T> CmsgSpace<T> {
+    pub fn new() -> Self {
+        unsafe { mem::zeroed() }
+    }
+}
+
+/// Ancillary data sent with `sendmsg` or received by `recvmsg`
+///
+/// [Further reading](http://man7.org/linux/man-pages/man3/cmsg.3.html)
+pub enum ControlMessage<'a> {
+    /// File descriptors to pass to the peer (`SCM_RIGHTS`). Received
+    /// descriptors are new descriptors in the receiving process.
+    ScmRights(&'a [Fd]),
+    /// The credentials of the sending process (`SCM_CREDENTIALS`). They are
+    /// only delivered if the receiver enabled `sockopt::PassCred`, and the
+    /// kernel checks them unless the sender is privileged.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ScmCredentials(ucred),
+    /// The time a datagram was received by the kernel (`SCM_TIMESTAMP`).
+    /// Only delivered if the receiver enabled `sockopt::ReceiveTimestamp`;
+    /// it cannot be sent.
+    ScmTimestamp(TimeVal),
+    /// The local address and interface a datagram arrived on
+    /// (`IP_PKTINFO`). Only delivered if the receiver enabled
+    /// `sockopt::IpPktInfo`. When sent, selects the source address and
+    /// interface of the datagram.
+    Ipv4PacketInfo(in_pktinfo),
+    /// The local address and interface a datagram arrived on
+    /// (`IPV6_PKTINFO`). Only delivered if the receiver enabled
+    /// `sockopt::Ipv6RecvPktInfo`. When sent, selects the source address and
+    /// interface of the datagram.
+    Ipv6PacketInfo(in6_pktinfo),
+    /// An error from the error queue of an IPv4 socket (`IP_RECVERR`), read
+    /// with `MSG_ERRQUEUE`. Errors are only queued if the receiver enabled
+    /// `sockopt::IpRecvErr`; it cannot be sent.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Ipv4RecvErr(sock_extended_err),
+    /// An error from the error queue of an IPv6 socket (`IPV6_RECVERR`),
+    /// read with `MSG_ERRQUEUE`. Errors are only queued if the receiver
+    /// enabled `sockopt::Ipv6RecvErr`; it cannot be sent.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Ipv6RecvErr(sock_extended_err),
+}
+
+impl<'a> ControlMessage<'a> {
+    fn level(&self) -> c_int {
+        match *self {
+            ControlMessage::ScmRights(..) => SOL_SOCKET,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            ControlMessage::ScmCredentials(..) => SOL_SOCKET,
+            ControlMessage::ScmTimestamp(..) => SOL_SOCKET,
+            ControlMessage::Ipv4PacketInfo(..) => IPPROTO_IP,
+            ControlMessage::Ipv6PacketInfo(..) => IPPROTO_IPV6,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            ControlMessage::Ipv4RecvErr(..) => IPPROTO_IP,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            ControlMessage::Ipv6RecvErr(..) => IPPROTO_IPV6,
+        }
+    }
+
+    fn cmsg_type(&self) -> c_int {
+        match *self {
+            ControlMessage::ScmRights(..) => SCM_RIGHTS,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            ControlMessage::ScmCredentials(..) => SCM_CREDENTIALS,
+            ControlMessage::ScmTimestamp(..) => SCM_TIMESTAMP,
+            ControlMessage::Ipv4PacketInfo(..) => IP_PKTINFO,
+            ControlMessage::Ipv6PacketInfo(..) => IPV6_PKTINFO,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            ControlMessage::Ipv4RecvErr(..) => IP_RECVERR,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            ControlMessage::Ipv6RecvErr(..) => IPV6_RECVERR,
+        }
+    }
+
+    // The length of the data, without the header
+    fn len(&self) -> usize {
+        match *self {
+            ControlMessage::ScmRights(fds) => fds.len() * mem::size_of::<Fd>(),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            ControlMessage::ScmCredentials(..) => mem::size_of::<ucred>(),
+            ControlMessage::ScmTimestamp(..) => mem::size_of::<TimeVal>(),
+            ControlMessage::Ipv4PacketInfo(..) => mem::size_of::<in_pktinfo>(),
+            ControlMessage::Ipv6PacketInfo(..) => mem::size_of::<in6_pktinfo>(),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            ControlMessage::Ipv4RecvErr(..) |
+            ControlMessage::Ipv6RecvErr(..) => mem::size_of::<sock_extended_err>(),
+        }
+    }
+
+    // The number of bytes this message takes up in a control buffer,
+    // including padding, like `CMSG_SPACE`
+    fn space(&self) -> usize {
+        cmsg_data_offset() + cmsg_align(self.len())
+    }
+
+    // Writes the header and data to `buf`, which must be aligned for a
+    // `cmsghdr` and have room for `self.space()` bytes
+    unsafe fn encode_into(&self, buf: *mut u8) {
+        let hdr = buf as *mut ffi::cmsghdr;
+        (*hdr).cmsg_len = (cmsg_data_offset() + self.len()) as ffi::controllen_t;
+        (*hdr).cmsg_level = self.level();
+        (*hdr).cmsg_type = self.cmsg_type();
+
+        let data = buf.offset(cmsg_data_offset() as isize);
+
+        match *self {
+            ControlMessage::ScmRights(fds) => {
+                ptr::copy_nonoverlapping(fds.as_ptr() as *const u8, data, self.len());
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            ControlMessage::ScmCredentials(ref cred) => {
+                ptr::copy_nonoverlapping(cred as *const ucred as *const u8, data, self.len());
+            }
+            ControlMessage::ScmTimestamp(ref tv) => {
+                ptr::copy_nonoverlapping(tv as *const TimeVal as *const u8, data, self.len());
+            }
+            ControlMessage::Ipv4PacketInfo(ref info) => {
+                ptr::copy_nonoverlapping(info as *const in_pktinfo as *const u8, data, self.len());
+            }
+            ControlMessage::Ipv6PacketInfo(ref info) => {
+                ptr::copy_nonoverlapping(info as *const in6_pktinfo as *const u8, data, self.len());
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            ControlMessage::Ipv4RecvErr(ref err) |
+            ControlMessage::Ipv6RecvErr(ref err) => {
+                ptr::copy_nonoverlapping(err as *const sock_extended_err as *const u8, data, self.len());
+            }
+        }
+    }
+
+    // Decodes the data of a received message, `None` if the type is not
+    // supported
+    unsafe fn decode(level: c_int, ty: c_int, data: &'a [u8]) -> Option<ControlMessage<'a>> {
+        match (level, ty) {
+            (SOL_SOCKET, SCM_RIGHTS) => {
+                let fds = slice::from_raw_parts(
+                    data.as_ptr() as *const Fd,
+                    data.len() / mem::size_of::<Fd>());
+                Some(ControlMessage::ScmRights(fds))
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            (SOL_SOCKET, SCM_CREDENTIALS) if data.len() >= mem::size_of::<ucred>() => {
+                Some(ControlMessage::ScmCredentials(ptr::read(data.as_ptr() as *const ucred)))
+            }
+            (SOL_SOCKET, SCM_TIMESTAMP) if data.len() >= mem::size_of::<TimeVal>() => {
+                Some(ControlMessage::ScmTimestamp(ptr::read(data.as_ptr() as *const TimeVal)))
+            }
+            (IPPROTO_IP, IP_PKTINFO) if data.len() >= mem::size_of::<in_pktinfo>() => {
+                Some(ControlMessage::Ipv4PacketInfo(ptr::read(data.as_ptr() as *const in_pktinfo)))
+            }
+            (IPPROTO_IPV6, IPV6_PKTINFO) if data.len() >= mem::size_of::<in6_pktinfo>() => {
+                Some(ControlMessage::Ipv6PacketInfo(ptr::read(data.as_ptr() as *const in6_pktinfo)))
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            (IPPROTO_IP, IP_RECVERR) if data.len() >= mem::size_of::<sock_extended_err>() => {
+                Some(ControlMessage::Ipv4RecvErr(ptr::read(data.as_ptr() as *const sock_extended_err)))
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            (IPPROTO_IPV6, IPV6_RECVERR) if data.len() >= mem::size_of::<sock_extended_err>() => {
+                Some(ControlMessage::Ipv6RecvErr(ptr::read(data.as_ptr() as *const sock_extended_err)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Iterates over the control messages received by `recvmsg`, in the order
+/// the kernel stored them, like `CMSG_NXTHDR`. Messages of an unsupported
+/// type are skipped.
+pub struct CmsgIterator<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Iterator for CmsgIterator<'a> {
+    type Item = ControlMessage<'a>;
+
+    fn next(&mut self) -> Option<ControlMessage<'a>> {
+        loop {
+            if self.buf.len() < cmsg_data_offset() {
+                return None;
+            }
+
+            let (level, ty, len) = unsafe {
+                let hdr = &*(self.buf.as_ptr() as *const ffi::cmsghdr);
+                (hdr.cmsg_level, hdr.cmsg_type, hdr.cmsg_len as usize)
+            };
+
+            // A truncated or malformed header ends the iteration
+            if len < cmsg_data_offset() || len > self.buf.len() {
+                return None;
+            }
+
+            // A message without data is valid, it decodes to an empty one
+            let data = &self.buf[cmsg_data_offset()..len];
+            let next = cmsg_align(len);
+
+            self.buf = if next < self.buf.len() { &self.buf[next..] } else { &[] };
+
+            if let Some(msg) = unsafe { ControlMessage::decode(level, ty, data) } {
+                return Some(msg);
+            }
+        }
+    }
+}
+
+/// A buffer of encoded control messages, ready to be sent with
+/// `sendmsg_control`. Each message is padded and aligned like `CMSG_SPACE`,
+/// so the headers can be walked with `CMSG_FIRSTHDR` and `CMSG_NXTHDR`.
+pub struct ControlMessageBuilder {
+    // Backed by `size_t` so that the headers are suitably aligned
+    buf: Vec<size_t>,
+    len: usize,
+}
+
+impl ControlMessageBuilder {
+    pub fn new() -> ControlMessageBuilder {
+        ControlMessageBuilder { buf: Vec::new(), len: 0 }
+    }
+
+    /// Append `cmsg` after the messages already in the buffer
+    pub fn push(&mut self, cmsg: &ControlMessage) -> &mut ControlMessageBuilder {
+        let end = self.len + cmsg.space();
+        let word = mem::size_of::<size_t>();
+        let words = (end + word - 1) / word;
+
+        if words > self.buf.len() {
+            let more = words - self.buf.len();
+            self.buf.extend(iter::repeat(0).take(more));
+        }
+
+        unsafe {
+            cmsg.encode_into((self.buf.as_mut_ptr() as *mut u8).offset(self.len as isize));
+        }
+
+        self.len = end;
+        self
+    }
+
+    /// The total size of the messages, to be used as `msg_controllen`
+    pub fn controllen(&self) -> usize {
+        self.len
+    }
+
+    /// The encoded messages, including the padding after each of them
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.buf.as_ptr() as *const u8, self.len) }
+    }
+
+    /// Iterate over the messages in the buffer
+    pub fn cmsgs(&self) -> CmsgIterator {
+        CmsgIterator { buf: self.as_bytes() }
+    }
+}
+
+/// The result of a `recvmsg` call
+pub struct RecvMsg<'a> {
+    /// The number of bytes received
+    pub bytes: usize,
+    /// The address of the sender, for sockets that are not connected
+    pub address: Option<SockAddr>,
+    /// Flags set on the received message, e.g. `MSG_TRUNC` when the data did
+    /// not fit in the buffers, or `MSG_CTRUNC` when the control data did not
+    pub flags: SockMessageFlags,
+    cmsg_buffer: &'a [u8],
+}
+
+impl<'a> RecvMsg<'a> {
+    /// Iterate over the control messages that were received
+    pub fn cmsgs(&self) -> CmsgIterator<'a> {
+        CmsgIterator { buf: self.cmsg_buffer }
+    }
+}
+
+/// Receive a message from a socket, scattering the data over the buffers of
+/// `iov`. Check `flags` of the result to detect a message that was truncated.
+///
+/// Control messages are received into `cmsg_buffer`, if given, and can be
+/// read with `RecvMsg::cmsgs`.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/recvmsg.2.html)
+pub fn recvmsg<'a, T>(fd: Fd, iov: &mut [IoVec<&mut [u8]>], cmsg_buffer: Option<&'a mut CmsgSpace<T>>, flags: SockMessageFlags) -> Result<RecvMsg<'a>> {
+    unsafe {
+        let mut addr: sockaddr_storage = mem::zeroed();
+        let mut mhdr: ffi::msghdr = mem::zeroed();
+
+        let (cmsg_ptr, cmsg_len) = match cmsg_buffer {
+            Some(space) => (space as *mut CmsgSpace<T> as *mut u8, mem::size_of::<CmsgSpace<T>>()),
+            None => (ptr::null_mut(), 0),
+        };
+
+        mhdr.msg_name = &mut addr as *mut sockaddr_storage as *mut c_void;
+        mhdr.msg_namelen = mem::size_of::<sockaddr_storage>() as socklen_t;
+        mhdr.msg_iov = iov.as_mut_ptr() as *mut c_void;
+        mhdr.msg_iovlen = iov.len() as ffi::iovlen_t;
+        mhdr.msg_control = cmsg_ptr as *mut c_void;
+        mhdr.msg_controllen = cmsg_len as ffi::controllen_t;
+
+        let ret = ffi::recvmsg(fd, &mut mhdr, flags);
+
+        if ret < 0 {
+            return Err(Error::last());
+        }
+
+        let address = if mhdr.msg_namelen == 0 {
+            None
+        } else {
+            SockAddr::from_libc_sockaddr_len(
+                &addr as *const sockaddr_storage as *const sockaddr,
+                mhdr.msg_namelen)
+        };
+
+        let cmsg_buffer = if cmsg_ptr.is_null() {
+            &[][..]
+        } else {
+            slice::from_raw_parts(cmsg_ptr as *const u8, mhdr.msg_controllen as usize)
+        };
+
+        Ok(RecvMsg {
+            bytes: ret as usize,
+            address: address,
+            flags: mhdr.msg_flags,
+            cmsg_buffer: cmsg_buffer,
+        })
+    }
+}
+
+/// Send a message on a socket, gathering the data from the buffers of `iov`,
+/// along with the control messages in `cmsgs`. `addr` is the destination for
+/// sockets that are not connected. Returns the number of bytes sent.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/sendmsg.2.html)
+pub fn sendmsg(fd: Fd, iov: &[IoVec<&[u8]>], cmsgs: &[ControlMessage], flags: SockMessageFlags, addr: Option<&SockAddr>) -> Result<usize> {
+    let mut control = ControlMessageBuilder::new();
+
+    for cmsg in cmsgs {
+        control.push(cmsg);
+    }
+
+    sendmsg_control(fd, iov, &control, flags, addr)
+}
+
+/// Like `sendmsg`, but sends the control messages already encoded in
+/// `control`
+pub fn sendmsg_control(fd: Fd, iov: &[IoVec<&[u8]>], control: &ControlMessageBuilder, flags: SockMessageFlags, addr: Option<&SockAddr>) -> Result<usize> {
+    unsafe {
+        let mut mhdr: ffi::msghdr = mem::zeroed();
+
+        if let Some(addr) = addr {
+            let (ptr, len) = addr.as_ffi_pair();
+            mhdr.msg_name = ptr as *const sockaddr as *mut c_void;
+            mhdr.msg_namelen = len;
+        }
+
+        mhdr.msg_iov = iov.as_ptr() as *mut c_void;
+        mhdr.msg_iovlen = iov.len() as ffi::iovlen_t;
+
+        if control.controllen() > 0 {
+            mhdr.msg_control = control.as_bytes().as_ptr() as *mut c_void;
+            mhdr.msg_controllen = control.controllen() as ffi::controllen_t;
+        }
+
+        let ret = ffi::sendmsg(fd, &mhdr, flags);
+
+        if ret < 0 {
+            return Err(Error::last());
+        }
+
+        Ok(ret as usize)
+    }
+}
+
+// The number of buffers `sendmsg_slices` can describe without allocating
+const STACK_IOVECS: usize = 8;
+
+/// Like `sendmsg`, but gathers the data from plain slices. Up to 8 buffers
+/// are described by an array on the stack, only more than that allocate.
+pub fn sendmsg_slices(fd: Fd, bufs: &[&[u8]], cmsgs: &[ControlMessage], flags: SockMessageFlags, addr: Option<&SockAddr>) -> Result<usize> {
+    if bufs.len() <= STACK_IOVECS {
+        // An all-zero `IoVec` is an empty buffer
+        let mut iov: [IoVec<&[u8]>; STACK_IOVECS] = unsafe { mem::zeroed() };
+
+        for (v, buf) in iov.iter_mut().zip(bufs.iter()) {
+            *v = IoVec::from_slice(*buf);
+        }
+
+        sendmsg(fd, &iov[..bufs.len()], cmsgs, flags, addr)
+    } else {
+        let iov: Vec<IoVec<&[u8]>> = bufs.iter().map(|buf| IoVec::from_slice(*buf)).collect();
+
+        sendmsg(fd, &iov, cmsgs, flags, addr)
+    }
+}
+
+/// A datagram received by `recvmmsg`
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub struct RecvMmsgData {
+    /// The number of bytes received into the buffer
+    pub bytes: usize,
+    /// The address of the sender, for sockets that are not connected
+    pub address: Option<SockAddr>,
+    /// Flags set on the received message, e.g. `MSG_TRUNC`
+    pub flags: SockMessageFlags,
+}
+
+/// Receive several datagrams with one call, one into each of `bufs`. Returns
+/// the datagrams that were received, which may be fewer than `bufs.len()`;
+/// only the first buffers are filled. None at all, e.g. on a non-blocking
+/// socket without pending datagrams, is an empty result rather than `EAGAIN`.
+///
+/// A `timeout` of `None` blocks for at least one datagram, by adding
+/// `MSG_WAITFORONE` to `flags`. The kernel only checks the timeout after each
+/// datagram, so on a blocking socket it does not bound the wait for the
+/// first one, and only stops the call early together with `MSG_WAITFORONE`
+/// or a non-blocking socket.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/recvmmsg.2.html)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn recvmmsg(fd: Fd, bufs: &mut [&mut [u8]], flags: SockMessageFlags, timeout: Option<TimeSpec>) -> Result<Vec<RecvMmsgData>> {
+    unsafe {
+        let mut iovs: Vec<IoVec<&mut [u8]>> = bufs.iter_mut().map(|buf| IoVec::from_mut_slice(&mut **buf)).collect();
+        let mut addrs: Vec<sockaddr_storage> = iovs.iter().map(|_| mem::zeroed()).collect();
+        let mut hdrs: Vec<ffi::mmsghdr> = iovs.iter().map(|_| mem::zeroed()).collect();
+
+        for ((hdr, iov), addr) in hdrs.iter_mut().zip(iovs.iter_mut()).zip(addrs.iter_mut()) {
+            hdr.msg_hdr.msg_name = addr as *mut sockaddr_storage as *mut c_void;
+            hdr.msg_hdr.msg_namelen = mem::size_of::<sockaddr_storage>() as socklen_t;
+            hdr.msg_hdr.msg_iov = iov as *mut IoVec<&mut [u8]> as *mut c_void;
+            hdr.msg_hdr.msg_iovlen = 1;
+        }
+
+        let mut timeout = timeout;
+        let (timeout_ptr, flags) = match timeout {
+            Some(ref mut ts) => (ts as *mut TimeSpec, flags),
+            None => (ptr::null_mut(), flags | MSG_WAITFORONE),
+        };
+
+        let ret = ffi::recvmmsg(fd, hdrs.as_mut_ptr(), hdrs.len() as c_uint, flags, timeout_ptr);
+
+        if ret < 0 {
+            return match Error::last() {
+                Error::Sys(Errno::EAGAIN) => Ok(Vec::new()),
+                e => Err(e),
+            };
+        }
+
+        let received = hdrs.iter().zip(addrs.iter()).take(ret as usize).map(|(hdr, addr)| {
+            let address = if hdr.msg_hdr.msg_namelen == 0 {
+                None
+            } else {
+                SockAddr::from_libc_sockaddr_len(
+                    addr as *const sockaddr_storage as *const sockaddr,
+                    hdr.msg_hdr.msg_namelen)
+            };
+
+            RecvMmsgData {
+                bytes: hdr.msg_len as usize,
+                address: address,
+                flags: hdr.msg_hdr.msg_flags,
+            }
+        }).collect();
+
+        Ok(received)
+    }
+}
+
+/// Send several datagrams with one call, each of `bufs` as one datagram to
+/// `addr`, or to the peer of a connected socket if `None`. Returns the number
+/// of datagrams sent, which may be fewer than `bufs.len()`; only an error on
+/// the first one is returned as an error.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/sendmmsg.2.html)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn sendmmsg(fd: Fd, bufs: &[&[u8]], addr: Option<&SockAddr>, flags: SockMessageFlags) -> Result<usize> {
+    unsafe {
+        let mut iovs: Vec<IoVec<&[u8]>> = bufs.iter().map(|buf| IoVec::from_slice(*buf)).collect();
+        let mut hdrs: Vec<ffi::mmsghdr> = iovs.iter().map(|_| mem::zeroed()).collect();
+
+        let (name, namelen) = match addr {
+            Some(addr) => {
+                let (ptr, len) = addr.as_ffi_pair();
+                (ptr as *const sockaddr as *mut c_void, len)
+            }
+            None => (ptr::null_mut(), 0),
+        };
+
+        for (hdr, iov) in hdrs.iter_mut().zip(iovs.iter_mut()) {
+            hdr.msg_hdr.msg_name = name;
+            hdr.msg_hdr.msg_namelen = namelen;
+            hdr.msg_hdr.msg_iov = iov as *mut IoVec<&[u8]> as *mut c_void;
+            hdr.msg_hdr.msg_iovlen = 1;
+        }
+
+        let ret = ffi::sendmmsg(fd, hdrs.as_mut_ptr(), hdrs.len() as c_uint, flags);
+
+        if ret < 0 {
+            return Err(Error::last());
+        }
+
+        Ok(ret as usize)
+    }
+}
+
+/// Create a pair of connected Unix sockets of type `SOCK_SEQPACKET`, which
+/// keep the boundaries of the messages sent over them. Together with
+/// `send_frame` and `recv_frame` this carries whole messages without a
+/// framing protocol. Not supported by every platform, e.g. OS X fails with
+/// `EPROTONOSUPPORT`.
+pub fn seqpacket_pair() -> Result<(Fd, Fd)> {
+    socketpair(AddressFamily::Unix, SockType::SeqPacket, SOCK_CLOEXEC)
+}
+
+/// Send `frame` as one message on a message oriented socket, such as one
+/// created by `seqpacket_pair`. Frames too large for the socket fail with
+/// `EMSGSIZE`, as does a short write, which can only happen when `fd` is a
+/// stream socket.
+pub fn send_frame(fd: Fd, frame: &[u8]) -> Result<()> {
+    let sent = try!(send(fd, frame, 0));
+
+    // Messages are sent whole or not at all
+    if sent != frame.len() {
+        return Err(Error::Sys(Errno::EMSGSIZE));
+    }
+
+    Ok(())
+}
+
+/// Receive one message into `buf`, replacing its contents and growing it as
+/// needed to hold the whole message. Returns the length of the message.
+///
+/// A length of 0 is either an empty frame or, on a connected socket, the
+/// peer having closed its end.
+pub fn recv_frame(fd: Fd, buf: &mut Vec<u8>) -> Result<usize> {
+    let mut size = cmp::max(buf.capacity(), 256);
+
+    // Peek until the buffer is large enough for the message not to be
+    // truncated
+    loop {
+        buf.clear();
+        buf.extend(iter::repeat(0).take(size));
+
+        let msg = try!(recvmsg(fd, &mut [IoVec::from_mut_slice(&mut buf[..])],
+                               None::<&mut CmsgSpace<()>>, MSG_PEEK));
+
+        if msg.flags & MSG_TRUNC == 0 {
+            break;
+        }
+
+        size *= 2;
+    }
+
+    let len = try!(recv(fd, &mut buf[..], 0));
+    buf.truncate(len);
+
+    Ok(len)
+}
+
 pub fn sendto(fd: Fd, buf: &[u8], addr: &SockAddr, flags: SockMessageFlags) -> Result<usize> {
     let ret = unsafe {
         let (ptr, len) = addr.as_ffi_pair();
@@ -222,6 +1250,148 @@ pub struct linger {
     pub l_linger: c_int
 }
 
+/// Credentials of a process, as passed with `SCM_CREDENTIALS`
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ucred {
+    pub pid: pid_t,
+    pub uid: uid_t,
+    pub gid: gid_t,
+}
+
+/// The address and interface of an IPv4 datagram, as passed with
+/// `IP_PKTINFO`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct in_pktinfo {
+    pub ipi_ifindex: c_uint,
+    /// The local address the datagram was routed to
+    pub ipi_spec_dst: in_addr,
+    /// The destination address in the datagram's header
+    pub ipi_addr: in_addr,
+}
+
+impl in_pktinfo {
+    /// The destination address of the datagram
+    pub fn addr(&self) -> Ipv4Addr {
+        Ipv4Addr(self.ipi_addr)
+    }
+
+    /// The index of the interface the datagram arrived on
+    pub fn ifindex(&self) -> u32 {
+        self.ipi_ifindex as u32
+    }
+}
+
+impl fmt::Debug for in_pktinfo {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "in_pktinfo {{ ipi_ifindex: {}, ipi_spec_dst: {}, ipi_addr: {} }}",
+               self.ipi_ifindex, Ipv4Addr(self.ipi_spec_dst), Ipv4Addr(self.ipi_addr))
+    }
+}
+
+/// The address and interface of an IPv6 datagram, as passed with
+/// `IPV6_PKTINFO`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct in6_pktinfo {
+    pub ipi6_addr: in6_addr,
+    pub ipi6_ifindex: c_uint,
+}
+
+impl in6_pktinfo {
+    /// The destination address of the datagram
+    pub fn addr(&self) -> Ipv6Addr {
+        Ipv6Addr(self.ipi6_addr)
+    }
+
+    /// The index of the interface the datagram arrived on
+    pub fn ifindex(&self) -> u32 {
+        self.ipi6_ifindex as u32
+    }
+}
+
+impl fmt::Debug for in6_pktinfo {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "in6_pktinfo {{ ipi6_addr: {}, ipi6_ifindex: {} }}",
+               Ipv6Addr(self.ipi6_addr), self.ipi6_ifindex)
+    }
+}
+
+/// An error read from the error queue of a socket, see
+/// `ControlMessage::Ipv4RecvErr`
+///
+/// [Further reading](http://man7.org/linux/man-pages/man7/ip.7.html)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct sock_extended_err {
+    /// The error number, e.g. `ECONNREFUSED` for an ICMP port unreachable
+    pub ee_errno: u32,
+    /// Where the error came from, one of the `SO_EE_ORIGIN_*` constants
+    pub ee_origin: u8,
+    /// The ICMP type for errors from ICMP
+    pub ee_type: u8,
+    /// The ICMP code for errors from ICMP
+    pub ee_code: u8,
+    ee_pad: u8,
+    /// The discovered MTU for `EMSGSIZE` errors
+    pub ee_info: u32,
+    pub ee_data: u32,
+}
+
+/// Statistics of a TCP connection, as returned by `sockopt::TcpInfo`. Only
+/// the fields common to all kernel versions are included.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct tcp_info {
+    /// One of the `TCP_ESTABLISHED`, ... states
+    pub tcpi_state: u8,
+    pub tcpi_ca_state: u8,
+    pub tcpi_retransmits: u8,
+    pub tcpi_probes: u8,
+    pub tcpi_backoff: u8,
+    pub tcpi_options: u8,
+    /// The send and receive window scales, 4 bits each
+    pub tcpi_wscale: u8,
+    tcpi_flags: u8,
+
+    pub tcpi_rto: u32,
+    pub tcpi_ato: u32,
+    pub tcpi_snd_mss: u32,
+    pub tcpi_rcv_mss: u32,
+
+    pub tcpi_unacked: u32,
+    pub tcpi_sacked: u32,
+    pub tcpi_lost: u32,
+    pub tcpi_retrans: u32,
+    pub tcpi_fackets: u32,
+
+    pub tcpi_last_data_sent: u32,
+    pub tcpi_last_ack_sent: u32,
+    pub tcpi_last_data_recv: u32,
+    pub tcpi_last_ack_recv: u32,
+
+    pub tcpi_pmtu: u32,
+    pub tcpi_rcv_ssthresh: u32,
+    /// Smoothed round trip time, in microseconds
+    pub tcpi_rtt: u32,
+    /// Round trip time variance, in microseconds
+    pub tcpi_rttvar: u32,
+    pub tcpi_snd_ssthresh: u32,
+    /// Congestion window, in segments
+    pub tcpi_snd_cwnd: u32,
+    pub tcpi_advmss: u32,
+    pub tcpi_reordering: u32,
+
+    pub tcpi_rcv_rtt: u32,
+    pub tcpi_rcv_space: u32,
+
+    pub tcpi_total_retrans: u32,
+}
+
 /*
  *
  * ===== Socket Options =====
@@ -272,6 +1442,38 @@ pub fn setsockopt<O: SockOpt>(fd: Fd, level: SockLevel, opt: O, val: O::Set) ->
     opt.set(fd, level as c_int, val)
 }
 
+/// Reads the option `name` at `level` into `buf`, returning the length of
+/// the value. For options without a typed `SockOpt`; the caller is
+/// responsible for interpreting the bytes with the layout the kernel uses.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/getsockopt.2.html)
+pub fn getsockopt_raw(fd: Fd, level: c_int, name: c_int, buf: &mut [u8]) -> Result<usize> {
+    let mut len = buf.len() as socklen_t;
+
+    let res = unsafe {
+        ffi::getsockopt(fd, level, name, buf.as_mut_ptr() as *mut c_void, &mut len)
+    };
+
+    if res < 0 {
+        return Err(Error::last());
+    }
+
+    Ok(len as usize)
+}
+
+/// Sets the option `name` at `level` to the bytes of `val`. For options
+/// without a typed `SockOpt`; the caller is responsible for laying out the
+/// value the way the kernel expects it.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/setsockopt.2.html)
+pub fn setsockopt_raw(fd: Fd, level: c_int, name: c_int, val: &[u8]) -> Result<()> {
+    let res = unsafe {
+        ffi::setsockopt(fd, level, name, val.as_ptr() as *const c_void, val.len() as socklen_t)
+    };
+
+    from_ffi(res)
+}
+
 /// Get the address of the peer connected to the socket `fd`.
 ///
 /// [Further reading](http://man7.org/linux/man-pages/man2/getpeername.2.html)
@@ -308,26 +1510,98 @@ pub fn getsockname(fd: Fd) -> Result<SockAddr> {
     }
 }
 
+/// Bind the socket to port 0 on `ip` and return the address, including the
+/// port, that the kernel picked.
+pub fn bind_ephemeral(fd: Fd, ip: IpAddr) -> Result<InetAddr> {
+    try!(bind(fd, &SockAddr::Inet(InetAddr::new(ip, 0))));
+
+    match try!(getsockname(fd)) {
+        SockAddr::Inet(addr) => Ok(addr),
+        _ => Err(Error::Sys(Errno::EAFNOSUPPORT)),
+    }
+}
+
+/// Describes an existing socket, see `SocketInfo::query`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SocketInfo {
+    /// The type of the socket, `None` for types `SockType` does not know
+    pub socktype: Option<SockType>,
+    /// Whether the socket is listening for connections
+    pub accept_conn: bool,
+    /// The domain the socket was created with, `None` where `SO_DOMAIN` is
+    /// not supported or the family is not one `AddressFamily` knows
+    pub domain: Option<AddressFamily>,
+    /// The protocol the socket was created with, `None` where `SO_PROTOCOL`
+    /// is not supported
+    pub protocol: Option<c_int>,
+}
+
+impl SocketInfo {
+    /// Gathers everything the platform can tell about the socket `fd`.
+    pub fn query(fd: Fd) -> Result<SocketInfo> {
+        let (domain, protocol) = try!(query_domain_protocol(fd));
+
+        Ok(SocketInfo {
+            socktype: try!(getsockopt(fd, SockLevel::Socket, sockopt::SocketType)),
+            accept_conn: try!(getsockopt(fd, SockLevel::Socket, sockopt::AcceptConn)),
+            domain: domain,
+            protocol: protocol,
+        })
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn query_domain_protocol(fd: Fd) -> Result<(Option<AddressFamily>, Option<c_int>)> {
+    // Kernels older than 2.6.32 do not know these options
+    let domain = match getsockopt(fd, SockLevel::Socket, sockopt::SocketDomain) {
+        Ok(domain) => domain,
+        Err(Error::Sys(Errno::ENOPROTOOPT)) => return Ok((None, None)),
+        Err(e) => return Err(e),
+    };
+
+    let protocol = try!(getsockopt(fd, SockLevel::Socket, sockopt::SocketProtocol));
+
+    Ok((domain, Some(protocol)))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn query_domain_protocol(_: Fd) -> Result<(Option<AddressFamily>, Option<c_int>)> {
+    Ok((None, None))
+}
+
 pub unsafe fn sockaddr_storage_to_addr(
     addr: &sockaddr_storage,
     len: usize) -> Result<SockAddr> {
 
-    match addr.ss_family as c_int {
+    // Malformed addresses and unknown families fail with `EINVAL`, rather
+    // than panicking on data that came from the kernel
+    let ret = match addr.ss_family as c_int {
         consts::AF_INET => {
-            assert!(len as usize == mem::size_of::<sockaddr_in>());
-            let ret = *(addr as *const _ as *const sockaddr_in);
-            Ok(SockAddr::Inet(InetAddr::V4(ret)))
+            if len != mem::size_of::<sockaddr_in>() {
+                return Err(Error::Sys(Errno::EINVAL));
+            }
+
+            Some(SockAddr::Inet(InetAddr::V4(*(addr as *const _ as *const sockaddr_in))))
         }
         consts::AF_INET6 => {
-            assert!(len as usize == mem::size_of::<sockaddr_in6>());
-            Ok(SockAddr::Inet(InetAddr::V6((*(addr as *const _ as *const sockaddr_in6)))))
+            if len != mem::size_of::<sockaddr_in6>() {
+                return Err(Error::Sys(Errno::EINVAL));
+            }
+
+            Some(SockAddr::Inet(InetAddr::V6(*(addr as *const _ as *const sockaddr_in6))))
         }
-        consts::AF_UNIX => {
-            assert!(len as usize == mem::size_of::<sockaddr_un>());
-            Ok(SockAddr::Unix(UnixAddr(*(addr as *const _ as *const sockaddr_un))))
+        // The kernel only fills in the part of `sun_path` in use
+        consts::AF_UNIX if len <= mem::size_of::<sockaddr_un>() => {
+            SockAddr::from_libc_sockaddr_len(addr as *const _ as *const sockaddr, len as socklen_t)
         }
-        af => panic!("unexpected address family {}", af),
-    }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        consts::AF_NETLINK | consts::AF_VSOCK => {
+            SockAddr::from_libc_sockaddr_len(addr as *const _ as *const sockaddr, len as socklen_t)
+        }
+        _ => None,
+    };
+
+    ret.ok_or(Error::Sys(Errno::EINVAL))
 }
 
 #[test]
@@ -335,3 +1609,53 @@ pub fn test_struct_sizes() {
     use nixtest;
     nixtest::assert_size_of::<sockaddr_storage>("sockaddr_storage");
 }
+
+#[test]
+pub fn test_failed_accept_closes_fd() {
+    use unistd::{close, fork, pipe, read, write};
+    use unistd::Fork::{Child, Parent};
+    use sys::wait::waitpid;
+    use libc;
+
+    unsafe fn fail(_: &sockaddr_storage, _: usize) -> Result<SockAddr> {
+        Err(Error::Sys(Errno::EINVAL))
+    }
+
+    let listener = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    let addr = SockAddr::Inet(bind_ephemeral(listener, IpAddr::new_v4(127, 0, 0, 1)).unwrap());
+    listen(listener, 1).unwrap();
+
+    let client = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    connect(client, &addr).unwrap();
+
+    let (reader, writer) = pipe().unwrap();
+
+    // Other threads of the test harness create descriptors too, so the
+    // lowest free number is only predictable in a child process
+    match fork().unwrap() {
+        Child => {
+            let probe = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+            close(probe).unwrap();
+
+            // The connection is accepted into `probe`, then closed again
+            let failed = accept_with(listener, fail).is_err();
+            let again = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+            let ok = failed && again == probe;
+
+            write(writer, &[ok as u8]).unwrap();
+            unsafe { libc::_exit(0) }
+        }
+        Parent(child_pid) => {
+            close(writer).unwrap();
+
+            let mut buf = [0u8; 1];
+            assert_eq!(read(reader, &mut buf).unwrap(), 1);
+            assert_eq!(buf[0], 1);
+
+            waitpid(child_pid, None).unwrap();
+            close(reader).unwrap();
+            close(client).unwrap();
+            close(listener).unwrap();
+        }
+    }
+}
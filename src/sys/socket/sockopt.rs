@@ -1,5 +1,5 @@
 use {Result, Error, from_ffi};
-use super::{ffi, consts, SockOpt};
+use super::{ffi, consts, SockOpt, SockType};
 use errno::Errno;
 use fcntl::Fd;
 use sys::time::TimeVal;
@@ -9,6 +9,38 @@ use std::mem;
 // Helper to generate the sockopt accessors
 // TODO: Figure out how to ommit gets when not supported by opt
 macro_rules! sockopt_impl {
+    (GetOnly, $name:ident, $flag:path, $get_ty:ty, $getter:ty) => {
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name;
+
+        impl SockOpt for $name {
+            type Get = $get_ty;
+            type Set = ();
+
+            fn get(&self, fd: Fd, level: c_int) -> Result<$get_ty> {
+                unsafe {
+                    let mut getter: $getter = Get::blank();
+
+                    let res = ffi::getsockopt(
+                        fd, level, $flag,
+                        getter.ffi_ptr(),
+                        getter.ffi_len());
+
+                    if res < 0 {
+                        return Err(Error::Sys(Errno::last()));
+                    }
+
+                    Ok(getter.unwrap())
+                }
+            }
+
+            fn set(&self, _: Fd, _: c_int, _: ()) -> Result<()> {
+                // The option is read only
+                Err(Error::Sys(Errno::ENOPROTOOPT))
+            }
+        }
+    };
+
     ($name:ident, $flag:path, bool) => {
         sockopt_impl!($name, $flag, bool, GetBool, bool, SetBool);
     };
@@ -17,6 +49,14 @@ macro_rules! sockopt_impl {
         sockopt_impl!($name, $flag, u8, GetU8, u8, SetU8);
     };
 
+    ($name:ident, $flag:path, usize) => {
+        sockopt_impl!($name, $flag, usize, GetUsize, usize, SetUsize);
+    };
+
+    ($name:ident, $flag:path, c_int) => {
+        sockopt_impl!($name, $flag, c_int, GetStruct<c_int>, c_int, SetInt);
+    };
+
     ($name:ident, $flag:path, $ty:ty) => {
         sockopt_impl!($name, $flag, $ty, GetStruct<$ty>, &'a $ty, SetStruct<$ty>);
     };
@@ -75,9 +115,67 @@ sockopt_impl!(Linger, consts::SO_LINGER, super::linger);
 sockopt_impl!(IpAddMembership, consts::IP_ADD_MEMBERSHIP, super::ip_mreq);
 sockopt_impl!(IpDropMembership, consts::IP_DROP_MEMBERSHIP, super::ip_mreq);
 sockopt_impl!(IpMulticastTtl, consts::IP_MULTICAST_TTL, u8);
+sockopt_impl!(IpTtl, consts::IP_TTL, c_int);
+sockopt_impl!(Ipv6UnicastHops, consts::IPV6_UNICAST_HOPS, c_int);
+// The DSCP and ECN bits of outgoing packets. Some systems only allow
+// privileged processes to set the higher precedence classes.
+sockopt_impl!(IpTos, consts::IP_TOS, c_int);
+sockopt_impl!(Ipv6TClass, consts::IPV6_TCLASS, c_int);
+sockopt_impl!(IpPktInfo, consts::IP_PKTINFO, bool);
+sockopt_impl!(Ipv6RecvPktInfo, consts::IPV6_RECVPKTINFO, bool);
 sockopt_impl!(ReceiveTimeout, consts::SO_RCVTIMEO, TimeVal);
 sockopt_impl!(SendTimeout, consts::SO_SNDTIMEO, TimeVal);
 sockopt_impl!(Broadcast, consts::SO_BROADCAST, bool);
+sockopt_impl!(ReceiveTimestamp, consts::SO_TIMESTAMP, bool);
+sockopt_impl!(OobInline, consts::SO_OOBINLINE, bool);
+// Linux doubles the size when set and reports the doubled value
+sockopt_impl!(SndBuf, consts::SO_SNDBUF, usize);
+sockopt_impl!(RcvBuf, consts::SO_RCVBUF, usize);
+sockopt_impl!(RcvLowat, consts::SO_RCVLOWAT, c_int);
+// Linux does not allow changing SO_SNDLOWAT, setting it fails with ENOPROTOOPT
+sockopt_impl!(SndLowat, consts::SO_SNDLOWAT, c_int);
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+sockopt_impl!(NoSigPipe, consts::SO_NOSIGPIPE, bool);
+// `None` for types `SockType` does not know, e.g. `SOCK_PACKET`
+sockopt_impl!(GetOnly, SocketType, consts::SO_TYPE, Option<SockType>, GetSockType);
+sockopt_impl!(GetOnly, SocketError, consts::SO_ERROR, c_int, GetStruct<c_int>);
+sockopt_impl!(GetOnly, AcceptConn, consts::SO_ACCEPTCONN, bool, GetBool);
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(PassCred, consts::SO_PASSCRED, bool);
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(GetOnly, PeerCredentials, consts::SO_PEERCRED, super::ucred, GetStruct<super::ucred>);
+// `None` for families `AddressFamily` does not know, e.g. `AF_PACKET`
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(GetOnly, SocketDomain, consts::SO_DOMAIN, Option<super::AddressFamily>, GetAddressFamily);
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(GetOnly, SocketProtocol, consts::SO_PROTOCOL, c_int, GetStruct<c_int>);
+// Seconds to wait for data before waking the accept loop. The kernel rounds
+// the value up to a whole number of SYN-ACK retransmissions, so it may read
+// back larger than set.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(TcpDeferAccept, consts::TCP_DEFER_ACCEPT, c_int);
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(GetOnly, TcpInfo, consts::TCP_INFO, super::tcp_info, GetStruct<super::tcp_info>);
+// Like SndBuf and RcvBuf, but able to exceed the system limits. They need
+// CAP_NET_ADMIN, otherwise setting them fails with EPERM, and can only be
+// read back through SndBuf and RcvBuf.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(SndBufForce, consts::SO_SNDBUFFORCE, usize);
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(RcvBufForce, consts::SO_RCVBUFFORCE, usize);
+// Setting the fwmark needs CAP_NET_ADMIN, otherwise it fails with EPERM
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(Mark, consts::SO_MARK, u32, GetStruct<u32>, u32, SetU32);
+// Priorities above 6 need CAP_NET_ADMIN
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(Priority, consts::SO_PRIORITY, c_int);
+// Queue extended errors, read with MSG_ERRQUEUE
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(IpRecvErr, consts::IP_RECVERR, bool);
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(Ipv6RecvErr, consts::IPV6_RECVERR, bool);
+#[cfg(any(target_os = "linux", target_os = "android"))]
+sockopt_impl!(BindToDevice, consts::SO_BINDTODEVICE, String, GetString, &'a str, SetStr<'a>);
 
 /*
  *
@@ -105,7 +203,11 @@ struct GetStruct<T> {
 
 impl<T> Get<T> for GetStruct<T> {
     unsafe fn blank() -> Self {
-        mem::zeroed()
+        let mut getter: Self = mem::zeroed();
+        // The kernel writes at most `len` bytes, so it has to start out as
+        // the size of the value
+        getter.len = mem::size_of::<T>() as socklen_t;
+        getter
     }
 
     unsafe fn ffi_ptr(&mut self) -> *mut c_void {
@@ -147,7 +249,9 @@ struct GetBool {
 
 impl Get<bool> for GetBool {
     unsafe fn blank() -> Self {
-        mem::zeroed()
+        let mut getter: Self = mem::zeroed();
+        getter.len = mem::size_of::<c_int>() as socklen_t;
+        getter
     }
 
     unsafe fn ffi_ptr(&mut self) -> *mut c_void {
@@ -189,7 +293,9 @@ struct GetU8 {
 
 impl Get<u8> for GetU8 {
     unsafe fn blank() -> Self {
-        mem::zeroed()
+        let mut getter: Self = mem::zeroed();
+        getter.len = mem::size_of::<uint8_t>() as socklen_t;
+        getter
     }
 
     unsafe fn ffi_ptr(&mut self) -> *mut c_void {
@@ -223,3 +329,193 @@ impl Set<u8> for SetU8 {
         mem::size_of::<c_int>() as socklen_t
     }
 }
+
+struct SetInt {
+    val: c_int,
+}
+
+impl Set<c_int> for SetInt {
+    fn new(val: c_int) -> SetInt {
+        SetInt { val: val }
+    }
+
+    unsafe fn ffi_ptr(&self) -> *const c_void {
+        mem::transmute(&self.val)
+    }
+
+    unsafe fn ffi_len(&self) -> socklen_t {
+        mem::size_of::<c_int>() as socklen_t
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+struct SetU32 {
+    val: u32,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Set<u32> for SetU32 {
+    fn new(val: u32) -> SetU32 {
+        SetU32 { val: val }
+    }
+
+    unsafe fn ffi_ptr(&self) -> *const c_void {
+        mem::transmute(&self.val)
+    }
+
+    unsafe fn ffi_len(&self) -> socklen_t {
+        mem::size_of::<u32>() as socklen_t
+    }
+}
+
+// Sizes are passed as a c_int
+struct GetUsize {
+    len: socklen_t,
+    val: c_int,
+}
+
+impl Get<usize> for GetUsize {
+    unsafe fn blank() -> Self {
+        let mut getter: Self = mem::zeroed();
+        getter.len = mem::size_of::<c_int>() as socklen_t;
+        getter
+    }
+
+    unsafe fn ffi_ptr(&mut self) -> *mut c_void {
+        mem::transmute(&mut self.val)
+    }
+
+    unsafe fn ffi_len(&mut self) -> *mut socklen_t {
+        mem::transmute(&mut self.len)
+    }
+
+    unsafe fn unwrap(self) -> usize {
+        assert!(self.len as usize == mem::size_of::<c_int>(), "invalid getsockopt implementation");
+        self.val as usize
+    }
+}
+
+struct SetUsize {
+    val: c_int,
+}
+
+impl Set<usize> for SetUsize {
+    fn new(val: usize) -> SetUsize {
+        SetUsize { val: val as c_int }
+    }
+
+    unsafe fn ffi_ptr(&self) -> *const c_void {
+        mem::transmute(&self.val)
+    }
+
+    unsafe fn ffi_len(&self) -> socklen_t {
+        mem::size_of::<c_int>() as socklen_t
+    }
+}
+
+struct GetSockType {
+    len: socklen_t,
+    val: c_int,
+}
+
+impl Get<Option<SockType>> for GetSockType {
+    unsafe fn blank() -> Self {
+        let mut getter: Self = mem::zeroed();
+        getter.len = mem::size_of::<c_int>() as socklen_t;
+        getter
+    }
+
+    unsafe fn ffi_ptr(&mut self) -> *mut c_void {
+        mem::transmute(&mut self.val)
+    }
+
+    unsafe fn ffi_len(&mut self) -> *mut socklen_t {
+        mem::transmute(&mut self.len)
+    }
+
+    unsafe fn unwrap(self) -> Option<SockType> {
+        assert!(self.len as usize == mem::size_of::<c_int>(), "invalid getsockopt implementation");
+        SockType::from_i32(self.val)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+struct GetAddressFamily {
+    len: socklen_t,
+    val: c_int,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Get<Option<super::AddressFamily>> for GetAddressFamily {
+    unsafe fn blank() -> Self {
+        let mut getter: Self = mem::zeroed();
+        getter.len = mem::size_of::<c_int>() as socklen_t;
+        getter
+    }
+
+    unsafe fn ffi_ptr(&mut self) -> *mut c_void {
+        mem::transmute(&mut self.val)
+    }
+
+    unsafe fn ffi_len(&mut self) -> *mut socklen_t {
+        mem::transmute(&mut self.len)
+    }
+
+    unsafe fn unwrap(self) -> Option<super::AddressFamily> {
+        assert!(self.len as usize == mem::size_of::<c_int>(), "invalid getsockopt implementation");
+        super::AddressFamily::from_i32(self.val)
+    }
+}
+
+// Large enough for an interface name, `IFNAMSIZ`
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const IFNAMSIZ: usize = 16;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+struct GetString {
+    len: socklen_t,
+    val: [u8; IFNAMSIZ],
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Get<String> for GetString {
+    unsafe fn blank() -> Self {
+        GetString { len: IFNAMSIZ as socklen_t, val: [0; IFNAMSIZ] }
+    }
+
+    unsafe fn ffi_ptr(&mut self) -> *mut c_void {
+        mem::transmute(&mut self.val)
+    }
+
+    unsafe fn ffi_len(&mut self) -> *mut socklen_t {
+        mem::transmute(&mut self.len)
+    }
+
+    unsafe fn unwrap(self) -> String {
+        // The name may or may not include the terminating NUL
+        let val = &self.val[..self.len as usize];
+        let end = val.iter().position(|&b| b == 0).unwrap_or(val.len());
+
+        String::from_utf8_lossy(&val[..end]).into_owned()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+struct SetStr<'a> {
+    val: &'a str,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<'a> Set<&'a str> for SetStr<'a> {
+    fn new(val: &'a str) -> SetStr<'a> {
+        SetStr { val: val }
+    }
+
+    unsafe fn ffi_ptr(&self) -> *const c_void {
+        self.val.as_ptr() as *const c_void
+    }
+
+    unsafe fn ffi_len(&self) -> socklen_t {
+        self.val.len() as socklen_t
+    }
+}
@@ -1,5 +1,5 @@
 use std::{fmt, ops};
-use libc::{time_t, suseconds_t};
+use libc::{time_t, suseconds_t, c_long};
 
 #[repr(C)]
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -174,6 +174,54 @@ impl fmt::Display for TimeVal {
     }
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TimeSpec {
+    pub tv_sec: time_t,
+    pub tv_nsec: c_long,
+}
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+impl TimeSpec {
+    #[inline]
+    pub fn zero() -> TimeSpec {
+        TimeSpec::nanoseconds(0)
+    }
+
+    #[inline]
+    pub fn seconds(seconds: i64) -> TimeSpec {
+        TimeSpec { tv_sec: seconds as time_t, tv_nsec: 0 }
+    }
+
+    #[inline]
+    pub fn milliseconds(milliseconds: i64) -> TimeSpec {
+        let nanoseconds = milliseconds.checked_mul(1_000_000)
+            .expect("TimeSpec::milliseconds out of bounds");
+
+        TimeSpec::nanoseconds(nanoseconds)
+    }
+
+    /// Makes a new `TimeSpec` with given number of nanoseconds.
+    #[inline]
+    pub fn nanoseconds(nanoseconds: i64) -> TimeSpec {
+        let (secs, nanos) = div_mod_floor_64(nanoseconds, NANOS_PER_SEC);
+        TimeSpec { tv_sec: secs as time_t, tv_nsec: nanos as c_long }
+    }
+
+    pub fn num_seconds(&self) -> i64 {
+        if self.tv_sec < 0 && self.tv_nsec > 0 {
+            (self.tv_sec + 1) as i64
+        } else {
+            self.tv_sec as i64
+        }
+    }
+
+    pub fn num_nanoseconds(&self) -> i64 {
+        (self.tv_sec as i64) * NANOS_PER_SEC + self.tv_nsec as i64
+    }
+}
+
 #[inline]
 fn div_mod_floor_64(this: i64, other: i64) -> (i64, i64) {
     (div_floor_64(this, other), mod_floor_64(this, other))
@@ -204,7 +252,7 @@ fn div_rem_64(this: i64, other: i64) -> (i64, i64) {
 
 #[cfg(test)]
 mod test {
-    use super::TimeVal;
+    use super::{TimeSpec, TimeVal};
 
     #[test]
     pub fn test_time_val() {
@@ -230,4 +278,12 @@ mod test {
         assert_eq!(TimeVal::microseconds(42).to_string(), "0.000042 seconds");
         assert_eq!(TimeVal::seconds(-86401).to_string(), "-86401 seconds");
     }
+
+    #[test]
+    pub fn test_time_spec() {
+        assert_eq!(TimeSpec::milliseconds(1500), TimeSpec { tv_sec: 1, tv_nsec: 500_000_000 });
+        assert_eq!(TimeSpec::nanoseconds(-1), TimeSpec { tv_sec: -1, tv_nsec: 999_999_999 });
+        assert_eq!(TimeSpec::nanoseconds(-1).num_nanoseconds(), -1);
+        assert_eq!(TimeSpec::seconds(2).num_seconds(), 2);
+    }
 }
@@ -0,0 +1,105 @@
+use std::mem;
+use libc::c_int;
+use errno::Errno;
+use {Error, Result};
+use fcntl::Fd;
+use sys::time::TimeSpec;
+
+mod ffi {
+    use libc::c_int;
+    use super::ITimerSpec;
+
+    extern {
+        pub fn timerfd_create(clockid: c_int, flags: c_int) -> c_int;
+        pub fn timerfd_settime(fd: c_int, flags: c_int, new_value: *const ITimerSpec, old_value: *mut ITimerSpec) -> c_int;
+        pub fn timerfd_gettime(fd: c_int, curr_value: *mut ITimerSpec) -> c_int;
+    }
+}
+
+/// The clock used to mark the progress of a timer
+#[repr(i32)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClockId {
+    /// Settable system-wide wall clock
+    Realtime = 0,
+    /// Clock that cannot be set and is not affected by jumps in system time
+    Monotonic = 1,
+}
+
+bitflags!(
+    flags TimerFdFlag: c_int {
+        const TFD_CLOEXEC  = 0o2000000,
+        const TFD_NONBLOCK = 0o0004000,
+    }
+);
+
+bitflags!(
+    flags TimerSetTimeFlag: c_int {
+        // `value` is an absolute time on the timer's clock
+        const TFD_TIMER_ABSTIME = 1,
+    }
+);
+
+/// The expiration of a timer: it first expires after `value`, then every
+/// `interval`. A zero `value` disarms the timer.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ITimerSpec {
+    pub interval: TimeSpec,
+    pub value: TimeSpec,
+}
+
+impl ITimerSpec {
+    /// A timer that expires once after `value`
+    pub fn one_shot(value: TimeSpec) -> ITimerSpec {
+        ITimerSpec { interval: TimeSpec::zero(), value: value }
+    }
+
+    /// A timer that expires every `interval`, starting after `interval`
+    pub fn periodic(interval: TimeSpec) -> ITimerSpec {
+        ITimerSpec { interval: interval, value: interval }
+    }
+}
+
+/// Creates a timer that delivers expirations through a file descriptor.
+/// Reading it returns the number of expirations as a native endian `u64`.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/timerfd_create.2.html)
+pub fn timerfd_create(clock: ClockId, flags: TimerFdFlag) -> Result<Fd> {
+    let res = unsafe { ffi::timerfd_create(clock as c_int, flags.bits()) };
+
+    if res < 0 {
+        return Err(Error::Sys(Errno::last()));
+    }
+
+    Ok(res)
+}
+
+/// Arms or disarms the timer, returning its previous setting
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/timerfd_create.2.html)
+pub fn timerfd_settime(fd: Fd, flags: TimerSetTimeFlag, new_value: &ITimerSpec) -> Result<ITimerSpec> {
+    let mut old: ITimerSpec = unsafe { mem::zeroed() };
+    let res = unsafe { ffi::timerfd_settime(fd, flags.bits(), new_value, &mut old) };
+
+    if res < 0 {
+        return Err(Error::Sys(Errno::last()));
+    }
+
+    Ok(old)
+}
+
+/// Returns the setting of the timer, where `value` is the time until the next
+/// expiration
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/timerfd_create.2.html)
+pub fn timerfd_gettime(fd: Fd) -> Result<ITimerSpec> {
+    let mut curr: ITimerSpec = unsafe { mem::zeroed() };
+    let res = unsafe { ffi::timerfd_gettime(fd, &mut curr) };
+
+    if res < 0 {
+        return Err(Error::Sys(Errno::last()));
+    }
+
+    Ok(curr)
+}
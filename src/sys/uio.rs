@@ -23,6 +23,10 @@ mod ffi {
     }
 }
 
+/// Gather write: writes the buffers in order with a single call. Works on
+/// any file descriptor, including connected sockets, pipes and regular files.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/writev.2.html)
 pub fn writev(fd: Fd, iov: &[IoVec<&[u8]>]) -> Result<usize> {
     let res = unsafe { ffi::writev(fd, iov.as_ptr(), iov.len() as c_int) };
 
@@ -33,6 +37,9 @@ pub fn writev(fd: Fd, iov: &[IoVec<&[u8]>]) -> Result<usize> {
     return Ok(res as usize)
 }
 
+/// Scatter read: fills the buffers in order with a single call.
+///
+/// [Further reading](http://man7.org/linux/man-pages/man2/readv.2.html)
 pub fn readv(fd: Fd, iov: &mut [IoVec<&mut [u8]>]) -> Result<usize> {
     let res = unsafe { ffi::readv(fd, iov.as_ptr(), iov.len() as c_int) };
     if res < 0 {
@@ -184,6 +184,36 @@ pub fn close(fd: Fd) -> Result<()> {
     from_ffi(res)
 }
 
+/// Owns a file descriptor and closes it when dropped.
+pub struct OwnedFd(Fd);
+
+impl OwnedFd {
+    /// Takes ownership of `fd`, which must not be closed elsewhere.
+    pub fn new(fd: Fd) -> OwnedFd {
+        OwnedFd(fd)
+    }
+
+    pub fn as_raw_fd(&self) -> Fd {
+        self.0
+    }
+
+    /// Gives up ownership of the descriptor without closing it.
+    pub fn into_raw_fd(self) -> Fd {
+        let fd = self.0;
+        mem::forget(self);
+        fd
+    }
+}
+
+impl Drop for OwnedFd {
+    fn drop(&mut self) {
+        // The descriptor is released even when close fails with EINTR, so
+        // it must not be retried: the number may already be reused by
+        // another thread.
+        let _ = close(self.0);
+    }
+}
+
 pub fn read(fd: Fd, buf: &mut [u8]) -> Result<usize> {
     let res = unsafe { ffi::read(fd, buf.as_mut_ptr() as *mut c_void, buf.len() as size_t) };
 
@@ -237,7 +267,7 @@ pub fn pipe2(flags: OFlag) -> Result<(Fd, Fd)> {
 }
 
 fn pipe2_setflags(fd1: Fd, fd2: Fd, flags: OFlag) -> Result<()> {
-    let mut res = Ok(());
+    let mut res = Ok(0);
 
     if flags.contains(O_CLOEXEC) {
         res = res
@@ -1,3 +1,9 @@
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_eventfd;
+mod test_pthread;
+mod test_signal;
 mod test_socket;
 mod test_termios;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_timerfd;
 mod test_uio;
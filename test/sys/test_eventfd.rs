@@ -0,0 +1,12 @@
+use nix::sys::eventfd::*;
+use nix::unistd::close;
+
+#[test]
+fn test_eventfd_read_write() {
+    let fd = eventfd(0, EFD_CLOEXEC).unwrap();
+
+    eventfd_write(fd, 5).unwrap();
+    assert_eq!(eventfd_read(fd).unwrap(), 5);
+
+    close(fd).unwrap();
+}
@@ -0,0 +1,54 @@
+use nix::sys::pthread::*;
+use libc::{c_int, c_void};
+use std::collections::HashMap;
+use std::{mem, ptr, thread};
+
+extern {
+    fn pthread_create(thread: *mut Pthread, attr: *const c_void,
+                      start: extern fn(*mut c_void) -> *mut c_void,
+                      arg: *mut c_void) -> c_int;
+    fn pthread_join(thread: Pthread, ret: *mut *mut c_void) -> c_int;
+    fn pause() -> c_int;
+}
+
+#[test]
+fn test_thread_id_map_key() {
+    let mut map = HashMap::new();
+    map.insert(ThreadId::current(), "main");
+
+    assert_eq!(map.get(&ThreadId::current()), Some(&"main"));
+    assert_eq!(ThreadId::current(), ThreadId(pthread_self()));
+
+    let other = thread::spawn(|| ThreadId::current()).join().unwrap();
+    assert!(other != ThreadId::current());
+}
+
+#[test]
+fn test_pthread_cancel() {
+    // Start the thread directly in `pause` so that no Rust frames are
+    // unwound by the cancellation.
+    let mut thread: Pthread = 0;
+    let start: extern fn(*mut c_void) -> *mut c_void =
+        unsafe { mem::transmute(pause as unsafe extern fn() -> c_int) };
+    assert_eq!(unsafe { pthread_create(&mut thread, ptr::null(), start, ptr::null_mut()) }, 0);
+
+    thread::sleep_ms(50);
+    pthread_cancel(thread).unwrap();
+
+    let mut ret = ptr::null_mut();
+    assert_eq!(unsafe { pthread_join(thread, &mut ret) }, 0);
+    // PTHREAD_CANCELED
+    assert_eq!(ret as isize, -1);
+}
+
+#[test]
+fn test_set_cancel_state() {
+    thread::spawn(|| {
+        assert_eq!(set_cancel_state(false), Ok(true));
+        assert_eq!(set_cancel_state(true), Ok(false));
+
+        // Switch to asynchronous and back, each reporting the previous type
+        assert_eq!(set_cancel_type(true), Ok(false));
+        assert_eq!(set_cancel_type(false), Ok(true));
+    }).join().unwrap();
+}
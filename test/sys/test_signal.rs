@@ -0,0 +1,547 @@
+use libc::{self, c_int, c_void};
+use nix::sys::signal::*;
+use nix::sys::wait::waitpid;
+use nix::unistd::fork;
+use nix::unistd::Fork::*;
+use std::thread;
+use std::sync::atomic::{AtomicBool, AtomicIsize, ATOMIC_BOOL_INIT, ATOMIC_ISIZE_INIT};
+#[cfg(target_os = "linux")]
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT};
+use std::sync::atomic::Ordering::SeqCst;
+
+static SIGCHLD_CAUGHT: AtomicBool = ATOMIC_BOOL_INIT;
+static SIGCHLD_PID: AtomicIsize = ATOMIC_ISIZE_INIT;
+static mut SIGCHLD_INFO: Option<SigInfo> = None;
+
+static SIGUSR1_CAUGHT: AtomicBool = ATOMIC_BOOL_INIT;
+static mut SIGUSR1_INFO: Option<SigInfo> = None;
+
+// Only records the SIGCHLD of the child `test_sigchld_status` forked, other
+// tests fork children concurrently
+extern fn record_sigchld(_: c_int, info: *mut siginfo, _: *mut c_void) {
+    let info = unsafe { SigInfo::from_raw(info) };
+
+    if info.child_pid() as isize == SIGCHLD_PID.load(SeqCst) {
+        unsafe { SIGCHLD_INFO = Some(info); }
+        SIGCHLD_CAUGHT.store(true, SeqCst);
+    }
+}
+
+static SIGVTALRM_CAUGHT: AtomicBool = ATOMIC_BOOL_INIT;
+
+extern fn record_sigvtalrm(_: c_int) {
+    SIGVTALRM_CAUGHT.store(true, SeqCst);
+}
+
+extern fn record_sigusr1(_: c_int, info: *mut siginfo, _: *mut c_void) {
+    unsafe { SIGUSR1_INFO = Some(SigInfo::from_raw(info)); }
+    SIGUSR1_CAUGHT.store(true, SeqCst);
+}
+
+#[test]
+fn test_sigchld_status() {
+    use nix::unistd::{close, pipe, read, write};
+
+    // Restarting keeps the `waitpid` of other tests from failing with EINTR
+    let action = SigAction::new_siginfo(record_sigchld, SA_RESTART, SigSet::empty());
+    let old = sigaction(SIGCHLD, &action).unwrap();
+
+    // The child waits until its pid is known to the handler
+    let (reader, writer) = pipe().unwrap();
+
+    match fork().unwrap() {
+        Child => unsafe {
+            let mut buf = [0u8; 1];
+            let _ = read(reader, &mut buf);
+            libc::_exit(3)
+        },
+        Parent(child_pid) => {
+            SIGCHLD_PID.store(child_pid as isize, SeqCst);
+            write(writer, b"x").unwrap();
+            close(reader).unwrap();
+            close(writer).unwrap();
+
+            for _ in 0..100 {
+                if SIGCHLD_CAUGHT.load(SeqCst) {
+                    break;
+                }
+                thread::sleep_ms(10);
+            }
+
+            sigaction(SIGCHLD, &old).unwrap();
+            let info = unsafe { SIGCHLD_INFO }.expect("SIGCHLD was not caught");
+
+            assert_eq!(info.signo(), SIGCHLD);
+            assert_eq!(info.child_pid(), child_pid);
+            assert_eq!(info.status(), 3);
+
+            waitpid(child_pid, None).unwrap();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_sigevent_signal() {
+    let signo = rt_signal(1);
+    let notify = SigevNotify::SigevSignal { signo: signo, value: 42 };
+    let sev = SigEvent::new(notify);
+
+    let raw = sev.sigevent();
+    assert_eq!(raw.sigev_signo, signo);
+    assert_eq!(raw.sigev_value as isize, 42);
+    assert_eq!(sev.notify(), notify);
+
+    assert_eq!(SigEvent::new(SigevNotify::SigevNone).notify(), SigevNotify::SigevNone);
+}
+
+#[cfg(target_os = "linux")]
+static TIMER_FIRED: [AtomicUsize; 2] = [ATOMIC_USIZE_INIT, ATOMIC_USIZE_INIT];
+
+#[cfg(target_os = "linux")]
+extern fn record_timer(_: c_int, info: *mut siginfo, _: *mut c_void) {
+    let info = unsafe { SigInfo::from_raw(info) };
+
+    if info.reason() == SigCode::Timer && info.overrun() == 0 {
+        // Remember the id of each timer, offset by one so 0 means unset
+        let id = info.timer_id() as usize + 1;
+        if TIMER_FIRED[0].compare_and_swap(0, id, SeqCst) != 0 {
+            TIMER_FIRED[1].store(id, SeqCst);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_siginfo_timer_id() {
+    use nix::sys::time::TimeSpec;
+    use nix::sys::timerfd::{ClockId, ITimerSpec};
+    use std::ptr;
+
+    #[link(name = "rt")]
+    extern {
+        fn timer_create(clock: c_int, sev: *mut sigevent, id: *mut *mut c_void) -> c_int;
+        fn timer_settime(id: *mut c_void, flags: c_int, new: *const ITimerSpec, old: *mut ITimerSpec) -> c_int;
+        fn timer_delete(id: *mut c_void) -> c_int;
+    }
+
+    // Handled rather than waited for, as the signals go to the process and
+    // may land on any thread
+    let signo = rt_signal(4);
+    let action = SigAction::new_siginfo(record_timer, SockFlag::empty(), SigSet::empty());
+    sigaction(signo, &action).unwrap();
+
+    let mut sev = SigEvent::new(SigevNotify::SigevSignal { signo: signo, value: 0 }).sigevent();
+    let mut ids = [ptr::null_mut(); 2];
+
+    for (i, id) in ids.iter_mut().enumerate() {
+        let spec = ITimerSpec::one_shot(TimeSpec::milliseconds(10 * (i as i64 + 1)));
+
+        unsafe {
+            assert_eq!(timer_create(ClockId::Monotonic as c_int, &mut sev, id), 0);
+            assert_eq!(timer_settime(*id, 0, &spec, ptr::null_mut()), 0);
+        }
+    }
+
+    for _ in 0..100 {
+        if TIMER_FIRED[1].load(SeqCst) != 0 {
+            break;
+        }
+        thread::sleep_ms(10);
+    }
+
+    // The first timer expires first
+    for (fired, &id) in TIMER_FIRED.iter().zip(ids.iter()) {
+        assert_eq!(fired.load(SeqCst), id as usize + 1);
+        unsafe { timer_delete(id) };
+    }
+}
+
+#[test]
+fn test_describe_queued_signal() {
+    extern {
+        fn sigqueue(pid: libc::pid_t, sig: c_int, value: *mut c_void) -> c_int;
+    }
+
+    let action = SigAction::new_siginfo(record_sigusr1, SockFlag::empty(), SigSet::empty());
+    sigaction(SIGUSR1, &action).unwrap();
+
+    assert_eq!(unsafe { sigqueue(libc::getpid(), SIGUSR1, 7 as *mut c_void) }, 0);
+
+    for _ in 0..100 {
+        if SIGUSR1_CAUGHT.load(SeqCst) {
+            break;
+        }
+        thread::sleep_ms(10);
+    }
+
+    let info = unsafe { SIGUSR1_INFO }.expect("SIGUSR1 was not caught");
+    assert_eq!(info.reason(), SigCode::Queue);
+
+    let description = info.describe();
+    assert!(description.contains("SIGUSR1"), "{}", description);
+    assert!(description.contains("queue"), "{}", description);
+
+    let debug = format!("{:?}", info);
+    assert!(debug.contains("SIGUSR1"), "{}", debug);
+    assert!(debug.contains("Queue"), "{}", debug);
+    assert!(debug.contains(&*format!("pid: {}", unsafe { libc::getpid() })), "{}", debug);
+}
+
+fn thread_mask() -> SigSet {
+    pthread_sigmask(SigMaskHow::SIG_BLOCK, None).unwrap()
+}
+
+#[test]
+fn test_thread_block_scoped() {
+    let mut set = SigSet::empty();
+    set.add(SIGUSR1).unwrap();
+
+    assert!(!thread_mask().contains(SIGUSR1));
+
+    {
+        let _guard = thread_block_scoped(&set).unwrap();
+        assert!(thread_mask().contains(SIGUSR1));
+    }
+
+    assert!(!thread_mask().contains(SIGUSR1));
+}
+
+#[test]
+fn test_set_mask_round_trip() {
+    let old = get_mask().unwrap();
+    let new = old | SIGXFSZ;
+
+    assert_eq!(set_mask(&new).unwrap(), old);
+    assert_eq!(get_mask().unwrap(), new);
+    assert!(get_mask().unwrap() != old);
+
+    assert_eq!(set_mask(&old).unwrap(), new);
+    assert_eq!(get_mask().unwrap(), old);
+}
+
+#[test]
+fn test_sigprocmask() {
+    let set = SigSet::from_signals(&[SIGXCPU]);
+
+    let old = sigprocmask(SigMaskHow::SIG_BLOCK, Some(&set)).unwrap();
+    assert!(!old.contains(SIGXCPU));
+
+    let current = sigprocmask(SigMaskHow::SIG_BLOCK, None).unwrap();
+    assert!(current.contains(SIGXCPU));
+
+    sigprocmask(SigMaskHow::SIG_SETMASK, Some(&old)).unwrap();
+    assert!(!sigprocmask(SigMaskHow::SIG_BLOCK, None).unwrap().contains(SIGXCPU));
+}
+
+#[test]
+fn test_parse_signal() {
+    use nix::Error;
+    use nix::errno::Errno;
+
+    assert_eq!(parse_signal("SIGINT").unwrap(), SIGINT);
+    assert_eq!(parse_signal("TERM").unwrap(), SIGTERM);
+    assert_eq!(parse_signal("9").unwrap(), SIGKILL);
+    assert_eq!(signal_name(parse_signal("HUP").unwrap()), Some("SIGHUP"));
+
+    for s in ["SIGBOGUS", "bogus", "0", "-1", "", "SIG"].iter() {
+        assert_eq!(parse_signal(s).err(), Some(Error::Sys(Errno::EINVAL)));
+    }
+}
+
+#[test]
+fn test_sigset_default_is_empty() {
+    let set: SigSet = Default::default();
+
+    for &signum in [SIGHUP, SIGINT, SIGTERM, SIGUSR1, SIGCHLD].iter() {
+        assert!(!set.contains(signum));
+        assert!(!SigSet::new().contains(signum));
+    }
+}
+
+#[test]
+fn test_sigset_from_signals() {
+    let set = SigSet::from_signals(&[SIGINT, SIGTERM]);
+
+    assert!(set.contains(SIGINT));
+    assert!(set.contains(SIGTERM));
+    assert!(!set.contains(SIGHUP));
+}
+
+#[test]
+fn test_sigset_bitor_signal() {
+    assert!((SigSet::empty() | SIGINT).contains(SIGINT));
+
+    let set = SigSet::empty() | SIGINT | SIGTERM;
+    assert!(set.contains(SIGINT));
+    assert!(set.contains(SIGTERM));
+    assert!(!set.contains(SIGHUP));
+}
+
+#[test]
+fn test_sigset_all_except() {
+    let set = SigSet::all_except(&[SIGINT]);
+
+    assert!(set.contains(SIGTERM));
+    assert!(set.contains(SIGHUP));
+    assert!(!set.contains(SIGINT));
+}
+
+#[test]
+fn test_sigset_difference() {
+    let intended = SigSet::from_signals(&[SIGINT, SIGTERM]);
+    let actual = SigSet::from_signals(&[SIGTERM, SIGUSR1]);
+
+    let (only_intended, only_actual) = intended.difference(&actual);
+    assert_eq!(only_intended, SigSet::from_signals(&[SIGINT]));
+    assert_eq!(only_actual, SigSet::from_signals(&[SIGUSR1]));
+
+    let (none, also_none) = intended.difference(&intended);
+    assert_eq!(none, SigSet::empty());
+    assert_eq!(also_none, SigSet::empty());
+}
+
+#[test]
+fn test_sigset_raw_round_trip() {
+    let raw = SigSet::all().into_sigset_t();
+    let set = SigSet::from_sigset_t(raw);
+
+    assert!(set.contains(SIGUSR2));
+    assert!(set == SigSet::all());
+}
+
+#[test]
+fn test_sigaction_oneshot() {
+    extern {
+        fn raise(sig: c_int) -> c_int;
+    }
+
+    let action = SigAction::oneshot(record_sigvtalrm, SigSet::empty());
+    assert!(action.flags().contains(SA_RESETHAND));
+
+    let prev = sigaction(SIGVTALRM, &action).unwrap();
+    let installed = sigaction(SIGVTALRM, &action).unwrap();
+    assert!(installed.flags().contains(SA_RESETHAND));
+
+    assert_eq!(unsafe { raise(SIGVTALRM) }, 0);
+    assert!(SIGVTALRM_CAUGHT.load(SeqCst));
+
+    sigaction(SIGVTALRM, &prev).unwrap();
+}
+
+#[test]
+fn test_pthread_sigmask_read_only() {
+    thread::spawn(|| {
+        let set = SigSet::from_signals(&[SIGURG, SIGWINCH]);
+        pthread_sigmask(SigMaskHow::SIG_BLOCK, Some(&set)).unwrap();
+
+        // SIG_SETMASK without a set would clear the mask if it were applied
+        let mask = pthread_sigmask(SigMaskHow::SIG_SETMASK, None).unwrap();
+        assert!(mask.contains(SIGURG));
+        assert!(mask.contains(SIGWINCH));
+        assert!(get_mask().unwrap() == mask);
+    }).join().unwrap();
+}
+
+#[test]
+fn test_wait_for_shutdown() {
+    extern {
+        fn raise(sig: c_int) -> c_int;
+    }
+
+    thread::spawn(|| {
+        // The signal is directed at this thread, so it has to be blocked
+        // before it is raised
+        pthread_sigmask(SigMaskHow::SIG_BLOCK, Some(&SigSet::from_signals(&[SIGTERM]))).unwrap();
+        assert_eq!(unsafe { raise(SIGTERM) }, 0);
+
+        assert_eq!(wait_for_shutdown(), Ok(SIGTERM));
+        assert!(get_mask().unwrap().contains(SIGINT));
+    }).join().unwrap();
+}
+
+#[test]
+fn test_sigaction_query() {
+    extern fn ignore(_: c_int) {}
+
+    let action = SigAction::new(ignore, SA_RESTART, SigSet::empty());
+    let prev = sigaction(SIGINT, &action).unwrap();
+
+    // Querying twice sees the same, unchanged action
+    assert!(sigaction_query(SIGINT).unwrap().flags().contains(SA_RESTART));
+    assert!(sigaction_query(SIGINT).unwrap().flags().contains(SA_RESTART));
+
+    sigaction(SIGINT, &prev).unwrap();
+    assert!(!sigaction_query(SIGINT).unwrap().flags().contains(SA_RESTART));
+}
+
+#[test]
+fn test_sigaction_no_child_wait() {
+    use nix::unistd::{close, pipe, read, write};
+
+    extern fn ignore(_: c_int) {}
+
+    let (reader, writer) = pipe().unwrap();
+
+    // Other tests wait for their children, so only change SIGCHLD in a
+    // child process
+    match fork().unwrap() {
+        Child => {
+            let action = SigAction::no_child_wait(ignore, SigSet::empty());
+            let ok = action.flags().contains(SA_NOCLDWAIT) &&
+                sigaction(SIGCHLD, &action).is_ok() &&
+                sigaction_query(SIGCHLD).unwrap().flags().contains(SA_NOCLDWAIT);
+
+            write(writer, &[ok as u8]).unwrap();
+            unsafe { libc::_exit(0) }
+        }
+        Parent(child_pid) => {
+            let mut buf = [0u8; 1];
+            assert_eq!(read(reader, &mut buf).unwrap(), 1);
+            assert_eq!(buf[0], 1);
+
+            waitpid(child_pid, None).unwrap();
+            close(reader).unwrap();
+            close(writer).unwrap();
+        }
+    }
+}
+
+#[test]
+fn test_register_handler() {
+    use nix::Error;
+    use nix::errno::Errno;
+    use std::sync::Arc;
+
+    extern {
+        fn raise(sig: c_int) -> c_int;
+    }
+
+    static DROPPED: AtomicBool = ATOMIC_BOOL_INIT;
+
+    struct Guard;
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            DROPPED.store(true, SeqCst);
+        }
+    }
+
+    let caught = Arc::new(AtomicBool::new(false));
+    let flag = caught.clone();
+    let guard = Guard;
+
+    let token = register_handler(SIGTTIN, Box::new(move |signum| {
+        let _ = &guard;
+        assert_eq!(signum, SIGTTIN);
+        flag.store(true, SeqCst);
+    })).unwrap();
+
+    match register_handler(SIGTTIN, Box::new(|_| {})) {
+        Err(Error::Sys(Errno::EBUSY)) => {}
+        _ => panic!("a second handler should not be registered"),
+    }
+
+    assert_eq!(unsafe { raise(SIGTTIN) }, 0);
+    assert!(caught.load(SeqCst));
+
+    drop(token);
+
+    // The closure is leaked, since a trampoline may still be running it
+    assert!(!DROPPED.load(SeqCst));
+
+    // The slot is free again once the token is gone
+    drop(register_handler(SIGTTIN, Box::new(|_| {})).unwrap());
+}
+
+#[test]
+fn test_sigset_wait() {
+    extern {
+        fn raise(sig: c_int) -> c_int;
+    }
+
+    let mut set = SigSet::empty();
+    set.add(SIGUSR2).unwrap();
+
+    // raise targets the calling thread, so block it here first to keep it
+    // pending until the wait
+    let _guard = thread_block_scoped(&set).unwrap();
+    assert_eq!(unsafe { raise(SIGUSR2) }, 0);
+
+    assert_eq!(set.wait().unwrap(), SIGUSR2);
+    assert!(thread_mask().contains(SIGUSR2));
+}
+
+#[test]
+fn test_signal_registry() {
+    extern {
+        fn raise(sig: c_int) -> c_int;
+    }
+
+    // Both are ignored by default, so a stray delivery elsewhere is harmless
+    let mut registry = SignalRegistry::new().unwrap();
+    registry.add(SIGWINCH).unwrap();
+    registry.add(SIGURG).unwrap();
+
+    assert!(SignalRegistry::new().is_err());
+
+    assert_eq!(unsafe { raise(SIGURG) }, 0);
+    assert_eq!(unsafe { raise(SIGWINCH) }, 0);
+
+    assert_eq!(registry.drain(), vec![SIGURG, SIGWINCH]);
+    assert!(registry.drain().is_empty());
+}
+
+#[test]
+fn test_process_exists() {
+    assert_eq!(process_exists(unsafe { libc::getpid() }), Ok(true));
+
+    // Well beyond any pid_max
+    assert_eq!(process_exists(i32::max_value()), Ok(false));
+}
+
+#[test]
+fn test_sigset_debug() {
+    let mut set = SigSet::empty();
+    set.add(SIGINT).unwrap();
+    set.add(SIGTERM).unwrap();
+
+    assert_eq!(set.iter().collect::<Vec<_>>(), vec![SIGINT, SIGTERM]);
+    assert_eq!(format!("{:?}", set), "{SIGINT, SIGTERM}");
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn test_sigset_debug_rt_signal() {
+    let mut set = SigSet::empty();
+    set.add(rt_signal(2)).unwrap();
+
+    assert_eq!(set.iter().collect::<Vec<_>>(), vec![sigrtmin() + 2]);
+    assert!(format!("{:?}", set).contains("SIGRTMIN+2"));
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn test_signal_stream() {
+    extern {
+        fn raise(sig: c_int) -> c_int;
+    }
+
+    // A signal no other test sends, so that no process-directed signal of
+    // another test ends up in the stream
+    let signo = rt_signal(5);
+
+    let mut set = SigSet::empty();
+    set.add(signo).unwrap();
+
+    // Blocks nothing, but restores the mask afterwards as the stream leaves
+    // the set blocked
+    let _guard = thread_block_scoped(&SigSet::empty()).unwrap();
+    let mut stream = SignalStream::new(set).unwrap();
+
+    // Sent to this thread only, which has it blocked now
+    assert_eq!(unsafe { raise(signo) }, 0);
+
+    let info = stream.next().unwrap().unwrap();
+    assert_eq!(info.signo(), signo);
+}
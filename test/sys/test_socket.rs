@@ -1,4 +1,12 @@
-use nix::sys::socket::{InetAddr, UnixAddr, getsockname};
+use nix::Error;
+use nix::errno::Errno;
+use nix::sys::socket::{AddressFamily, CmsgSpace, InetAddr, IpAddr, UnixAddr, SockAddr, SockFlag, SockLevel, SockType};
+use nix::sys::socket::{connect_timeout, getsockname, getsockopt, listen, recvmsg, send, send_nosignal};
+use nix::sys::socket::{setsockopt, socket, socketpair, sockopt, MSG_CTRUNC, MSG_TRUNC};
+use nix::sys::socket::{sockaddr_in, sockaddr_in6, sockaddr_storage};
+use nix::sys::uio::IoVec;
+use nix::sys::time::{TimeSpec, TimeVal};
+use nix::unistd::close;
 use std::{mem, net};
 use std::path::Path;
 use std::str::FromStr;
@@ -48,3 +56,1512 @@ pub fn test_getsockname() {
 
     assert_eq!(addr, res.to_str());
 }
+
+#[test]
+pub fn test_sockaddr_storage_to_addr_invalid() {
+    use nix::sys::socket::sockaddr_storage_to_addr;
+    use libc::sa_family_t;
+
+    let mut storage: sockaddr_storage = unsafe { mem::zeroed() };
+
+    // An unknown family
+    storage.ss_family = 250 as sa_family_t;
+    let res = unsafe { sockaddr_storage_to_addr(&storage, mem::size_of::<sockaddr_storage>()) };
+    assert_eq!(res.err(), Some(Error::Sys(Errno::EINVAL)));
+
+    // An IPv4 address of the wrong length
+    storage.ss_family = AddressFamily::Inet as sa_family_t;
+    let res = unsafe { sockaddr_storage_to_addr(&storage, 2) };
+    assert_eq!(res.err(), Some(Error::Sys(Errno::EINVAL)));
+}
+
+#[test]
+pub fn test_getsockopt_socket_type() {
+    let fd = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let ty = getsockopt(fd, SockLevel::Socket, sockopt::SocketType).unwrap();
+
+    assert_eq!(ty, Some(SockType::Datagram));
+    close(fd).unwrap();
+}
+
+#[test]
+pub fn test_inet_addr_canonicalize() {
+    let mapped = InetAddr::from_str("[::ffff:192.0.2.1]:80").unwrap();
+    let v4 = InetAddr::from_str("192.0.2.1:80").unwrap();
+
+    assert!(mapped != v4);
+    assert!(mapped.canonicalize() == v4);
+    assert!(v4.canonicalize() == v4);
+
+    let v6 = InetAddr::from_str("[2001:db8::1]:80").unwrap();
+    assert!(v6.canonicalize() == v6);
+}
+
+#[test]
+pub fn test_lowat() {
+    let fd = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+
+    setsockopt(fd, SockLevel::Socket, sockopt::RcvLowat, 16).unwrap();
+    assert_eq!(getsockopt(fd, SockLevel::Socket, sockopt::RcvLowat).unwrap(), 16);
+    assert!(getsockopt(fd, SockLevel::Socket, sockopt::SndLowat).unwrap() > 0);
+
+    close(fd).unwrap();
+}
+
+#[test]
+pub fn test_inet_addr_from_host_port() {
+    let v4 = InetAddr::from_host_port("192.0.2.1", 80).unwrap();
+    assert_eq!(v4.to_str(), "192.0.2.1:80");
+
+    let v6 = InetAddr::from_host_port("2001:db8::1", 443).unwrap();
+    assert_eq!(v6.to_str(), "[2001:db8::1]:443");
+
+    match InetAddr::from_host_port("localhost", 80) {
+        Err(Error::Sys(Errno::EINVAL)) => {}
+        _ => panic!("host names should not be resolved"),
+    }
+}
+
+#[test]
+pub fn test_socket_info_query() {
+    use nix::sys::socket::{bind_ephemeral, SocketInfo};
+
+    let fd = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    bind_ephemeral(fd, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+
+    let info = SocketInfo::query(fd).unwrap();
+    assert_eq!(info.socktype, Some(SockType::Stream));
+    assert!(!info.accept_conn);
+
+    listen(fd, 1).unwrap();
+
+    let info = SocketInfo::query(fd).unwrap();
+    assert_eq!(info.socktype, Some(SockType::Stream));
+    assert!(info.accept_conn);
+
+    if cfg!(any(target_os = "linux", target_os = "android")) {
+        assert_eq!(info.domain, Some(AddressFamily::Inet));
+        assert_eq!(info.protocol, Some(6));
+    }
+
+    close(fd).unwrap();
+}
+
+#[test]
+pub fn test_inet_addr_broadcast_network_v4() {
+    use nix::sys::socket::Ipv4Addr;
+
+    let addr = Ipv4Addr::new(192, 168, 1, 10);
+    let netmask = Ipv4Addr::new(255, 255, 255, 0);
+
+    assert_eq!(InetAddr::broadcast_v4(addr, netmask).octets(), [192, 168, 1, 255]);
+    assert_eq!(InetAddr::network_v4(addr, netmask).octets(), [192, 168, 1, 0]);
+
+    let netmask = Ipv4Addr::new(255, 255, 240, 0);
+    assert_eq!(InetAddr::broadcast_v4(addr, netmask).octets(), [192, 168, 15, 255]);
+    assert_eq!(InetAddr::network_v4(addr, netmask).octets(), [192, 168, 0, 0]);
+}
+
+#[test]
+pub fn test_ip_net_contains() {
+    use nix::sys::socket::IpNet;
+
+    let addr = |s: &str| InetAddr::from_str(s).unwrap();
+
+    let net = IpNet::new(addr("10.0.0.0:0"), 8).unwrap();
+    assert!(net.contains(&addr("10.1.2.3:80")));
+    assert!(!net.contains(&addr("11.0.0.1:80")));
+    assert!(!net.contains(&addr("[::ffff:10.1.2.3]:80")));
+    assert_eq!(net.to_string(), "10.0.0.0/8");
+
+    let net = IpNet::new(addr("192.168.16.0:0"), 20).unwrap();
+    assert!(net.contains(&addr("192.168.31.255:0")));
+    assert!(!net.contains(&addr("192.168.32.0:0")));
+
+    let net = IpNet::new(addr("[2001:db8::]:0"), 32).unwrap();
+    assert!(net.contains(&addr("[2001:db8:1::1]:0")));
+    assert!(!net.contains(&addr("[2001:db9::1]:0")));
+    assert!(!net.contains(&addr("10.1.2.3:0")));
+
+    assert!(IpNet::new(addr("0.0.0.0:0"), 0).unwrap().contains(&addr("1.2.3.4:0")));
+    assert!(IpNet::new(addr("1.2.3.4:0"), 32).unwrap().contains(&addr("1.2.3.4:5")));
+
+    assert_eq!(IpNet::new(addr("10.0.0.0:0"), 33).err(), Some(Error::Sys(Errno::EINVAL)));
+    assert_eq!(IpNet::new(addr("[::]:0"), 129).err(), Some(Error::Sys(Errno::EINVAL)));
+}
+
+#[test]
+pub fn test_ip_net_hosts() {
+    use nix::sys::socket::IpNet;
+
+    let addr = |s: &str| InetAddr::from_str(s).unwrap();
+    let hosts = |net: &str, len: u8| -> Vec<String> {
+        IpNet::new(addr(net), len).unwrap().hosts().map(|a| a.to_str()).collect()
+    };
+
+    assert_eq!(hosts("10.0.0.0:80", 30), ["10.0.0.1:80", "10.0.0.2:80"]);
+    assert_eq!(hosts("10.0.0.3:80", 31), ["10.0.0.2:80", "10.0.0.3:80"]);
+    assert_eq!(hosts("10.0.0.3:80", 32), ["10.0.0.3:80"]);
+    assert_eq!(hosts("10.0.0.255:0", 23), hosts("10.0.1.0:0", 23));
+    assert_eq!(hosts("10.0.0.0:0", 23).len(), 510);
+    assert_eq!(hosts("[2001:db8::]:0", 127), ["[2001:db8::]:0", "[2001:db8::1]:0"]);
+
+    let mut big = IpNet::new(addr("[::]:0"), 0).unwrap().hosts();
+    assert_eq!(big.nth(256).unwrap().to_str(), "[::100]:0");
+}
+
+#[test]
+pub fn test_sockopt_raw() {
+    use nix::sys::socket::{getsockopt_raw, setsockopt_raw, SOL_SOCKET, SO_REUSEADDR};
+
+    let fd = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    assert!(!getsockopt(fd, SockLevel::Socket, sockopt::ReuseAddr).unwrap());
+
+    let on: i32 = 1;
+    let bytes: [u8; 4] = unsafe { mem::transmute(on) };
+    setsockopt_raw(fd, SOL_SOCKET, SO_REUSEADDR, &bytes).unwrap();
+    assert!(getsockopt(fd, SockLevel::Socket, sockopt::ReuseAddr).unwrap());
+
+    let mut buf = [0u8; 4];
+    assert_eq!(getsockopt_raw(fd, SOL_SOCKET, SO_REUSEADDR, &mut buf).unwrap(), 4);
+    assert_eq!(buf, bytes);
+
+    close(fd).unwrap();
+}
+
+#[test]
+pub fn test_inet_addr_std_v6_round_trip() {
+    let ip = net::Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+    let std = net::SocketAddr::V6(net::SocketAddrV6::new(ip, 8080, 7, 3));
+
+    let addr = InetAddr::from_std(&std);
+    match addr {
+        InetAddr::V6(ref sa) => {
+            assert_eq!(sa.sin6_flowinfo, 7);
+            assert_eq!(sa.sin6_scope_id, 3);
+        }
+        _ => panic!("expected an IPv6 address"),
+    }
+
+    assert_eq!(addr.to_std(), std);
+}
+
+#[test]
+pub fn test_zeroed_addrs() {
+    assert_eq!(InetAddr::zeroed_v4().to_str(), "0.0.0.0:0");
+    assert_eq!(InetAddr::zeroed_v6().to_str(), "[::]:0");
+
+    assert!(SockAddr::zeroed(AddressFamily::Inet) == SockAddr::Inet(InetAddr::zeroed_v4()));
+    assert_eq!(SockAddr::zeroed(AddressFamily::Inet6).family(), AddressFamily::Inet6);
+
+    match SockAddr::zeroed(AddressFamily::Unix) {
+        SockAddr::Unix(addr) => assert_eq!(addr.path(), Path::new("")),
+        _ => panic!("expected a unix address"),
+    }
+}
+
+#[test]
+pub fn test_inet_addr_as_ipv4() {
+    let addr = InetAddr::new(IpAddr::new_v4(127, 0, 0, 1), 80);
+
+    assert_eq!(addr.as_ipv4().map(|ip| ip.octets()), Some([127, 0, 0, 1]));
+    assert!(addr.as_ipv6().is_none());
+}
+
+#[test]
+pub fn test_inet_addr_as_ipv6() {
+    let addr = InetAddr::new(IpAddr::new_v6(0, 0, 0, 0, 0, 0, 0, 1), 80);
+
+    assert_eq!(addr.as_ipv6().map(|ip| ip.segments()), Some([0, 0, 0, 0, 0, 0, 0, 1]));
+    assert!(addr.as_ipv4().is_none());
+}
+
+#[test]
+pub fn test_connect_timeout() {
+    // 10.255.255.1 is not routable, so the SYN goes unanswered
+    let addr = SockAddr::new_inet(InetAddr::new(IpAddr::new_v4(10, 255, 255, 1), 80));
+    let fd = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+
+    match connect_timeout(fd, &addr, TimeSpec::milliseconds(100)) {
+        Err(Error::Sys(Errno::ETIMEDOUT)) => {}
+        // Hosts without a default route fail straight away
+        Err(Error::Sys(Errno::ENETUNREACH)) | Err(Error::Sys(Errno::EHOSTUNREACH)) => {}
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    close(fd).unwrap();
+}
+
+#[test]
+pub fn test_connect_timeout_huge() {
+    use nix::sys::socket::bind_ephemeral;
+
+    let listener = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    let addr = SockAddr::Inet(bind_ephemeral(listener, IpAddr::new_v4(127, 0, 0, 1)).unwrap());
+    listen(listener, 1).unwrap();
+
+    // Effectively forever, far more milliseconds than fit in an `i64`
+    let fd = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    connect_timeout(fd, &addr, TimeSpec::seconds(1 << 60)).unwrap();
+
+    close(fd).unwrap();
+    close(listener).unwrap();
+}
+
+#[test]
+pub fn test_send_nosignal() {
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Stream, SockFlag::empty()).unwrap();
+    close(b).unwrap();
+
+    assert_eq!(send_nosignal(a, b"hello", 0), Err(Error::Sys(Errno::EPIPE)));
+    close(a).unwrap();
+}
+
+#[test]
+pub fn test_inet_addr_bytes_v4() {
+    let addr = InetAddr::new(IpAddr::new_v4(192, 168, 1, 10), 8080);
+    let bytes = addr.to_bytes();
+
+    assert_eq!(bytes, vec![192, 168, 1, 10]);
+    assert!(InetAddr::from_bytes(&bytes, 8080).unwrap() == addr);
+}
+
+#[test]
+pub fn test_inet_addr_bytes_v6() {
+    let addr = InetAddr::new(IpAddr::new_v6(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x1234), 8080);
+    let bytes = addr.to_bytes();
+
+    assert_eq!(bytes, vec![0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0,
+                           0, 0, 0, 0, 0, 0, 0x12, 0x34]);
+    assert!(InetAddr::from_bytes(&bytes, 8080).unwrap() == addr);
+}
+
+#[test]
+pub fn test_inet_addr_tlv_v4() {
+    let addr = InetAddr::new(IpAddr::new_v4(192, 168, 1, 10), 8080);
+    let mut bytes = addr.to_tlv();
+
+    assert_eq!(bytes, vec![1, 192, 168, 1, 10, 0x1f, 0x90]);
+
+    // Trailing data is left alone
+    bytes.push(0xff);
+    let (decoded, used) = InetAddr::from_tlv(&bytes).unwrap();
+    assert!(decoded == addr);
+    assert_eq!(used, 7);
+}
+
+#[test]
+pub fn test_inet_addr_tlv_v6() {
+    let addr = InetAddr::new(IpAddr::new_v6(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x1234), 443);
+    let bytes = addr.to_tlv();
+
+    assert_eq!(bytes.len(), 19);
+    assert_eq!(bytes[0], 4);
+
+    let (decoded, used) = InetAddr::from_tlv(&bytes).unwrap();
+    assert!(decoded == addr);
+    assert_eq!(used, 19);
+}
+
+#[test]
+pub fn test_inet_addr_tlv_invalid() {
+    assert_eq!(InetAddr::from_tlv(&[]).err(), Some(Error::Sys(Errno::EINVAL)));
+    assert_eq!(InetAddr::from_tlv(&[3, 127, 0, 0, 1, 0, 80]).err(), Some(Error::Sys(Errno::EINVAL)));
+    assert_eq!(InetAddr::from_tlv(&[1, 127, 0, 0, 1, 0]).err(), Some(Error::Sys(Errno::EINVAL)));
+}
+
+#[test]
+pub fn test_inet_addr_from_bytes_invalid() {
+    assert_eq!(InetAddr::from_bytes(&[127, 0, 0], 80).err(), Some(Error::Sys(Errno::EINVAL)));
+}
+
+#[test]
+pub fn test_from_libc_sockaddr_len() {
+    let addr = SockAddr::new_inet(InetAddr::new(IpAddr::new_v4(127, 0, 0, 1), 80));
+
+    unsafe {
+        let (ptr, len) = addr.as_ffi_pair();
+
+        assert!(SockAddr::from_libc_sockaddr_len(ptr, len).unwrap() == addr);
+        assert!(SockAddr::from_libc_sockaddr_len(ptr, len - 1).is_none());
+        assert!(SockAddr::from_libc_sockaddr_len(ptr, 1).is_none());
+    }
+}
+
+// Unix sockets on OS X do not support SOCK_SEQPACKET
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_seqpacket_frames() {
+    use nix::sys::socket::{recv_frame, send_frame, seqpacket_pair};
+
+    let (a, b) = seqpacket_pair().unwrap();
+
+    // Larger than the initial buffer
+    let big = vec![7u8; 1000];
+    send_frame(a, b"first").unwrap();
+    send_frame(a, &big).unwrap();
+
+    let mut buf = Vec::new();
+    assert_eq!(recv_frame(b, &mut buf).unwrap(), 5);
+    assert_eq!(buf, b"first".to_vec());
+
+    assert_eq!(recv_frame(b, &mut buf).unwrap(), 1000);
+    assert_eq!(buf, big);
+
+    close(a).unwrap();
+    assert_eq!(recv_frame(b, &mut buf).unwrap(), 0);
+    close(b).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_send_frame_short_write() {
+    use nix::sys::socket::{send_frame, SOCK_NONBLOCK};
+
+    // A non-blocking stream socket takes as much as fits in its buffer
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Stream, SOCK_NONBLOCK).unwrap();
+    let huge = vec![0u8; 16 * 1024 * 1024];
+
+    assert_eq!(send_frame(a, &huge).err(), Some(Error::Sys(Errno::EMSGSIZE)));
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
+
+#[test]
+pub fn test_recvmsg_reports_truncation() {
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Datagram, SockFlag::empty()).unwrap();
+    send(a, b"12345678", 0).unwrap();
+
+    let mut buf = [0u8; 4];
+    let msg = recvmsg(b, &mut [IoVec::from_mut_slice(&mut buf)], None::<&mut CmsgSpace<()>>, 0).unwrap();
+
+    assert_eq!(msg.bytes, 4);
+    assert!(msg.flags & MSG_TRUNC != 0);
+    assert_eq!(&buf, b"1234");
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
+
+#[test]
+pub fn test_getsockopt_accept_conn() {
+    let fd = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    assert!(!getsockopt(fd, SockLevel::Socket, sockopt::AcceptConn).unwrap());
+
+    listen(fd, 1).unwrap();
+    assert!(getsockopt(fd, SockLevel::Socket, sockopt::AcceptConn).unwrap());
+
+    close(fd).unwrap();
+}
+
+#[test]
+pub fn test_sockopt_ip_ttl() {
+    let fd = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+
+    setsockopt(fd, SockLevel::Ip, sockopt::IpTtl, 16).unwrap();
+    assert_eq!(getsockopt(fd, SockLevel::Ip, sockopt::IpTtl).unwrap(), 16);
+
+    close(fd).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_abstract_sun_path_len() {
+    use nix::sys::socket::bind;
+    use libc;
+
+    let name = format!("nix-test-abstract-{}", unsafe { libc::getpid() });
+    let addr = UnixAddr::new_abstract(name.as_bytes()).unwrap();
+    let sock_addr = SockAddr::Unix(addr);
+
+    let fd = socket(AddressFamily::Unix, SockType::Stream, SockFlag::empty()).unwrap();
+    bind(fd, &sock_addr).unwrap();
+
+    match getsockname(fd).unwrap() {
+        SockAddr::Unix(actual) => {
+            assert_eq!(actual.as_abstract(), Some(name.as_bytes()));
+            assert_eq!(actual.len(), sock_addr.len());
+        }
+        _ => panic!("expected a unix address"),
+    }
+
+    close(fd).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SUN_PATH_LEN: usize = 108;
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const SUN_PATH_LEN: usize = 104;
+
+#[test]
+pub fn test_unix_addr_path_len_boundary() {
+    use std::iter;
+
+    // The terminating NUL has to fit as well
+    let longest: String = iter::repeat('a').take(SUN_PATH_LEN - 1).collect();
+    let addr = UnixAddr::new(Path::new(&longest)).unwrap();
+    assert_eq!(addr.path(), Path::new(&longest));
+
+    let too_long: String = iter::repeat('a').take(SUN_PATH_LEN).collect();
+    assert_eq!(UnixAddr::new(Path::new(&too_long)).err(), Some(Error::Sys(Errno::ENAMETOOLONG)));
+}
+
+#[test]
+pub fn test_unix_addr_from_raw() {
+    let addr = UnixAddr::new(Path::new("/tmp/sock")).unwrap();
+    assert_eq!(addr.path_len(), 9);
+
+    let copy = UnixAddr::from_raw(addr.0, addr.path_len()).unwrap();
+    assert!(copy == addr);
+    assert_eq!(copy.len(), addr.len());
+
+    assert_eq!(UnixAddr::from_raw(addr.0, SUN_PATH_LEN + 1).err(), Some(Error::Sys(Errno::EINVAL)));
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_unix_addr_abstract_len_boundary() {
+    // The leading NUL has to fit as well
+    let longest = vec![b'a'; SUN_PATH_LEN - 1];
+    let addr = UnixAddr::new_abstract(&longest).unwrap();
+    assert_eq!(addr.as_abstract(), Some(&longest[..]));
+
+    let too_long = vec![b'a'; SUN_PATH_LEN];
+    assert_eq!(UnixAddr::new_abstract(&too_long).err(), Some(Error::Sys(Errno::ENAMETOOLONG)));
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_unix_autobind() {
+    use nix::sys::socket::bind;
+    use libc;
+
+    let addr = UnixAddr::new_unnamed();
+    assert_eq!(addr.len() as usize, mem::size_of::<libc::sa_family_t>());
+
+    let fd = socket(AddressFamily::Unix, SockType::Datagram, SockFlag::empty()).unwrap();
+    bind(fd, &SockAddr::Unix(addr)).unwrap();
+
+    match getsockname(fd).unwrap() {
+        SockAddr::Unix(actual) => {
+            let name = actual.as_abstract().expect("expected an abstract address");
+            assert!(!name.is_empty());
+        }
+        _ => panic!("expected a unix address"),
+    }
+
+    close(fd).unwrap();
+}
+
+#[test]
+pub fn test_bind_connect_std() {
+    use nix::sys::socket::{accept, bind_std, connect_std};
+
+    let listener = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    bind_std(listener, FromStr::from_str("127.0.0.1:0").unwrap()).unwrap();
+    listen(listener, 1).unwrap();
+
+    let addr = match getsockname(listener).unwrap() {
+        SockAddr::Inet(addr) => addr.to_std(),
+        _ => panic!("expected an internet address"),
+    };
+    assert!(addr.port() != 0);
+
+    let client = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    connect_std(client, addr).unwrap();
+
+    close(accept(listener).unwrap()).unwrap();
+    close(client).unwrap();
+    close(listener).unwrap();
+}
+
+#[test]
+pub fn test_bind_ephemeral() {
+    use nix::sys::socket::bind_ephemeral;
+
+    let fd = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    let addr = bind_ephemeral(fd, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+
+    assert_eq!(addr.as_ipv4().unwrap().octets(), [127, 0, 0, 1]);
+    // Ports below 1024 are reserved and never handed out automatically
+    assert!(addr.port() >= 1024);
+    assert!(getsockname(fd).unwrap() == SockAddr::Inet(addr));
+
+    close(fd).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_scm_credentials() {
+    use nix::sys::socket::{sendmsg, ucred, ControlMessage};
+    use libc;
+
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Datagram, SockFlag::empty()).unwrap();
+    setsockopt(b, SockLevel::Socket, sockopt::PassCred, true).unwrap();
+
+    let cred = unsafe {
+        ucred { pid: libc::getpid(), uid: libc::getuid(), gid: libc::getgid() }
+    };
+
+    let iov = [IoVec::from_slice(b"hello")];
+    assert_eq!(sendmsg(a, &iov, &[ControlMessage::ScmCredentials(cred)], 0, None).unwrap(), 5);
+
+    let mut buf = [0u8; 5];
+    let mut space: CmsgSpace<ucred> = CmsgSpace::new();
+    let msg = recvmsg(b, &mut [IoVec::from_mut_slice(&mut buf)], Some(&mut space), 0).unwrap();
+    assert_eq!(msg.bytes, 5);
+
+    let mut received = None;
+    for cmsg in msg.cmsgs() {
+        if let ControlMessage::ScmCredentials(cred) = cmsg {
+            received = Some(cred);
+        }
+    }
+    assert_eq!(received, Some(cred));
+    assert_eq!(&buf, b"hello");
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
+
+#[test]
+pub fn test_recvfrom_flags_trunc() {
+    use nix::sys::socket::{bind_ephemeral, recvfrom_flags, sendto};
+
+    let rx = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let addr = bind_ephemeral(rx, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+
+    let tx = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let tx_addr = bind_ephemeral(tx, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+    assert_eq!(sendto(tx, b"0123456789", &SockAddr::Inet(addr), 0).unwrap(), 10);
+    assert_eq!(sendto(tx, b"0123", &SockAddr::Inet(addr), 0).unwrap(), 4);
+
+    let mut buf = [0u8; 4];
+    let (len, from, flags) = recvfrom_flags(rx, &mut buf, MSG_TRUNC).unwrap();
+    assert!(flags & MSG_TRUNC != 0);
+    assert!(from == SockAddr::Inet(tx_addr));
+    assert_eq!(&buf, b"0123");
+    if cfg!(any(target_os = "linux", target_os = "android")) {
+        assert_eq!(len, 10);
+    }
+
+    let (len, _, flags) = recvfrom_flags(rx, &mut buf, 0).unwrap();
+    assert_eq!(len, 4);
+    assert_eq!(flags & MSG_TRUNC, 0);
+
+    close(tx).unwrap();
+    close(rx).unwrap();
+}
+
+#[test]
+pub fn test_recv_with_addr_connected_udp() {
+    use nix::sys::socket::{bind_ephemeral, connect, recv_with_addr};
+
+    let a = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let a_addr = bind_ephemeral(a, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+    let b = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let b_addr = bind_ephemeral(b, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+
+    connect(a, &SockAddr::Inet(b_addr)).unwrap();
+    connect(b, &SockAddr::Inet(a_addr)).unwrap();
+
+    assert_eq!(send(b, b"hi", 0).unwrap(), 2);
+
+    let mut buf = [0u8; 4];
+    let (len, from) = recv_with_addr(a, &mut buf, 0).unwrap();
+    assert_eq!(len, 2);
+    assert_eq!(&buf[..2], &b"hi"[..]);
+    assert!(from == Some(SockAddr::Inet(b_addr)));
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
+
+#[test]
+pub fn test_recvfrom_into_reused_storage() {
+    use nix::sys::socket::{bind_ephemeral, recvfrom_into, sendto};
+
+    let rx = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let addr = bind_ephemeral(rx, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+
+    let mut storage: sockaddr_storage = unsafe { mem::zeroed() };
+    let mut buf = [0u8; 8];
+
+    for i in 0..3u8 {
+        let tx = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+        let tx_addr = bind_ephemeral(tx, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+        assert_eq!(sendto(tx, &[i], &SockAddr::Inet(addr), 0).unwrap(), 1);
+
+        assert_eq!(recvfrom_into(rx, &mut buf, &mut storage).unwrap(), 1);
+        assert_eq!(buf[0], i);
+        assert!(InetAddr::from_storage(&storage).unwrap() == tx_addr);
+
+        close(tx).unwrap();
+    }
+
+    close(rx).unwrap();
+}
+
+#[test]
+pub fn test_scm_timestamp() {
+    use nix::sys::socket::{bind_ephemeral, sendto, ControlMessage};
+
+    let rx = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let addr = bind_ephemeral(rx, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+    setsockopt(rx, SockLevel::Socket, sockopt::ReceiveTimestamp, true).unwrap();
+    assert!(getsockopt(rx, SockLevel::Socket, sockopt::ReceiveTimestamp).unwrap());
+
+    let tx = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    assert_eq!(sendto(tx, b"ping", &SockAddr::Inet(addr), 0).unwrap(), 4);
+
+    let mut buf = [0u8; 4];
+    let mut space: CmsgSpace<TimeVal> = CmsgSpace::new();
+    let msg = recvmsg(rx, &mut [IoVec::from_mut_slice(&mut buf)], Some(&mut space), 0).unwrap();
+    assert_eq!(msg.bytes, 4);
+
+    let mut received = None;
+    for cmsg in msg.cmsgs() {
+        if let ControlMessage::ScmTimestamp(tv) = cmsg {
+            received = Some(tv);
+        }
+    }
+
+    // Some time after 2001
+    let tv = received.expect("no timestamp received");
+    assert!(tv.tv_sec > 1000000000);
+
+    close(tx).unwrap();
+    close(rx).unwrap();
+}
+
+#[test]
+pub fn test_ip_pktinfo() {
+    use nix::sys::socket::{bind_ephemeral, in_pktinfo, sendto, ControlMessage};
+
+    let rx = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let port = bind_ephemeral(rx, IpAddr::new_v4(0, 0, 0, 0)).unwrap().port();
+    setsockopt(rx, SockLevel::Ip, sockopt::IpPktInfo, true).unwrap();
+    assert!(getsockopt(rx, SockLevel::Ip, sockopt::IpPktInfo).unwrap());
+
+    let tx = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let dst = InetAddr::new(IpAddr::new_v4(127, 0, 0, 1), port);
+    assert_eq!(sendto(tx, b"ping", &SockAddr::Inet(dst), 0).unwrap(), 4);
+
+    let mut buf = [0u8; 4];
+    let mut space: CmsgSpace<in_pktinfo> = CmsgSpace::new();
+    let msg = recvmsg(rx, &mut [IoVec::from_mut_slice(&mut buf)], Some(&mut space), 0).unwrap();
+    assert_eq!(msg.bytes, 4);
+
+    let mut received = None;
+    for cmsg in msg.cmsgs() {
+        if let ControlMessage::Ipv4PacketInfo(info) = cmsg {
+            received = Some(info);
+        }
+    }
+
+    let info = received.expect("no packet info received");
+    assert_eq!(info.addr().octets(), [127, 0, 0, 1]);
+    assert!(info.ifindex() > 0);
+
+    close(tx).unwrap();
+    close(rx).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_ip_recv_err() {
+    use nix::sys::socket::{bind_ephemeral, connect, sock_extended_err, ControlMessage};
+    use nix::sys::socket::{MSG_ERRQUEUE, SO_EE_ORIGIN_ICMP};
+    use std::thread;
+
+    // A port nothing listens on once the socket is closed
+    let closed = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let addr = bind_ephemeral(closed, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+    close(closed).unwrap();
+
+    let fd = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    setsockopt(fd, SockLevel::Ip, sockopt::IpRecvErr, true).unwrap();
+    assert!(getsockopt(fd, SockLevel::Ip, sockopt::IpRecvErr).unwrap());
+
+    connect(fd, &SockAddr::Inet(addr)).unwrap();
+    assert_eq!(send(fd, b"ping", 0).unwrap(), 4);
+
+    // Reading the error queue never blocks, give the ICMP error time to
+    // arrive
+    let mut received = None;
+
+    for _ in 0..100 {
+        let mut buf = [0u8; 4];
+        let mut space: CmsgSpace<sock_extended_err> = CmsgSpace::new();
+
+        match recvmsg(fd, &mut [IoVec::from_mut_slice(&mut buf)], Some(&mut space), MSG_ERRQUEUE) {
+            Ok(msg) => {
+                for cmsg in msg.cmsgs() {
+                    if let ControlMessage::Ipv4RecvErr(err) = cmsg {
+                        received = Some(err);
+                    }
+                }
+                break;
+            }
+            Err(Error::Sys(Errno::EAGAIN)) => thread::sleep_ms(10),
+            Err(e) => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    let err = received.expect("no error queued");
+    assert_eq!(err.ee_errno as i32, Errno::ECONNREFUSED as i32);
+    assert_eq!(err.ee_origin, SO_EE_ORIGIN_ICMP);
+    // Destination unreachable, port unreachable
+    assert_eq!((err.ee_type, err.ee_code), (3, 3));
+
+    close(fd).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_control_message_builder() {
+    use nix::sys::socket::{sendmsg_control, ucred, ControlMessage, ControlMessageBuilder, SOL_SOCKET};
+    use libc::{self, c_int, size_t};
+
+    // The layout of `struct cmsghdr` on Linux
+    #[repr(C)]
+    struct cmsghdr {
+        cmsg_len: size_t,
+        cmsg_level: c_int,
+        cmsg_type: c_int,
+    }
+
+    fn align(len: usize) -> usize {
+        let word = mem::size_of::<size_t>();
+        (len + word - 1) & !(word - 1)
+    }
+
+    // Like `CMSG_FIRSTHDR` and `CMSG_NXTHDR`
+    fn first_hdr(buf: &[u8]) -> Option<usize> {
+        if buf.len() >= mem::size_of::<cmsghdr>() { Some(0) } else { None }
+    }
+
+    fn next_hdr(buf: &[u8], offset: usize) -> Option<usize> {
+        let hdr = unsafe { &*(buf[offset..].as_ptr() as *const cmsghdr) };
+        let next = offset + align(hdr.cmsg_len as usize);
+        if next + mem::size_of::<cmsghdr>() <= buf.len() { Some(next) } else { None }
+    }
+
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Datagram, SockFlag::empty()).unwrap();
+    let cred = unsafe {
+        ucred { pid: libc::getpid(), uid: libc::getuid(), gid: libc::getgid() }
+    };
+    let fds = [b];
+
+    let mut control = ControlMessageBuilder::new();
+    control.push(&ControlMessage::ScmRights(&fds))
+           .push(&ControlMessage::ScmCredentials(cred));
+
+    let hdr_len = align(mem::size_of::<cmsghdr>());
+    let rights_space = hdr_len + align(mem::size_of::<c_int>());
+    let cred_space = hdr_len + align(mem::size_of::<ucred>());
+    assert_eq!(control.controllen(), rights_space + cred_space);
+
+    let buf = control.as_bytes();
+    assert_eq!(buf.len(), control.controllen());
+
+    let first = first_hdr(buf).unwrap();
+    assert_eq!(first, 0);
+    let hdr = unsafe { &*(buf[first..].as_ptr() as *const cmsghdr) };
+    assert_eq!(hdr.cmsg_len as usize, hdr_len + mem::size_of::<c_int>());
+    assert_eq!(hdr.cmsg_level, SOL_SOCKET);
+
+    let second = next_hdr(buf, first).unwrap();
+    assert_eq!(second, rights_space);
+    let hdr = unsafe { &*(buf[second..].as_ptr() as *const cmsghdr) };
+    assert_eq!(hdr.cmsg_len as usize, hdr_len + mem::size_of::<ucred>());
+    assert_eq!(hdr.cmsg_level, SOL_SOCKET);
+
+    assert!(next_hdr(buf, second).is_none());
+    assert_eq!(control.cmsgs().count(), 2);
+
+    let iov = [IoVec::from_slice(b"x")];
+    assert_eq!(sendmsg_control(a, &iov, &control, 0, None).unwrap(), 1);
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_recvmsg_multiple_cmsgs() {
+    use nix::fcntl::Fd;
+    use nix::sys::socket::{sendmsg, ucred, ControlMessage};
+    use nix::unistd::{pipe, read, write};
+    use libc;
+
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Datagram, SockFlag::empty()).unwrap();
+    setsockopt(b, SockLevel::Socket, sockopt::PassCred, true).unwrap();
+
+    let (r, w) = pipe().unwrap();
+    let cred = unsafe {
+        ucred { pid: libc::getpid(), uid: libc::getuid(), gid: libc::getgid() }
+    };
+
+    let iov = [IoVec::from_slice(b"x")];
+    let rights = [r];
+    let cmsgs = [ControlMessage::ScmRights(&rights), ControlMessage::ScmCredentials(cred)];
+    assert_eq!(sendmsg(a, &iov, &cmsgs, 0, None).unwrap(), 1);
+    close(r).unwrap();
+
+    let mut buf = [0u8; 1];
+    let mut space: CmsgSpace<([Fd; 1], CmsgSpace<ucred>)> = CmsgSpace::new();
+    let msg = recvmsg(b, &mut [IoVec::from_mut_slice(&mut buf)], Some(&mut space), 0).unwrap();
+    assert_eq!(msg.flags & MSG_CTRUNC, 0);
+
+    let (mut fds, mut creds) = (Vec::new(), Vec::new());
+    for cmsg in msg.cmsgs() {
+        match cmsg {
+            ControlMessage::ScmRights(received) => fds.extend(received.iter().cloned()),
+            ControlMessage::ScmCredentials(received) => creds.push(received),
+            _ => panic!("unexpected control message"),
+        }
+    }
+
+    assert_eq!(creds, vec![cred]);
+    assert_eq!(fds.len(), 1);
+
+    // The received descriptor is the read end of the pipe
+    write(w, b"!").unwrap();
+    assert_eq!(read(fds[0], &mut buf).unwrap(), 1);
+    assert_eq!(&buf, b"!");
+
+    close(fds[0]).unwrap();
+    close(w).unwrap();
+    close(a).unwrap();
+    close(b).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_peer_credentials() {
+    use libc;
+
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Stream, SockFlag::empty()).unwrap();
+
+    let cred = getsockopt(a, SockLevel::Socket, sockopt::PeerCredentials).unwrap();
+    assert_eq!(cred.uid, unsafe { libc::getuid() });
+    assert_eq!(cred.pid, unsafe { libc::getpid() });
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
+
+#[test]
+pub fn test_inet_addr_scope_id_round_trip() {
+    let addr = InetAddr::from_str("[fe80::1%2]:80").unwrap();
+    assert_eq!(addr.to_str(), "[fe80::1%2]:80");
+    assert!(InetAddr::from_str(&addr.to_str()).unwrap() == addr);
+
+    // The scope is only shown for link-local addresses
+    let addr = InetAddr::from_str("[::1]:80").unwrap();
+    assert_eq!(addr.to_str(), "[::1]:80");
+
+    let addr = InetAddr::from_str("127.0.0.1:80").unwrap();
+    assert!(InetAddr::from_str(&addr.to_str()).unwrap() == addr);
+
+    assert_eq!(InetAddr::from_str("[fe80::1%nix-no-such-if]:80").err(),
+               Some(Error::Sys(Errno::ENODEV)));
+    assert_eq!(InetAddr::from_str("[fe80::1%]:80").err(), Some(Error::Sys(Errno::EINVAL)));
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_inet_addr_scope_by_name() {
+    use nix::sys::socket::if_nametoindex;
+
+    let index = if_nametoindex("lo").unwrap();
+
+    match InetAddr::from_str("[fe80::1%lo]:80").unwrap() {
+        InetAddr::V6(sa) => assert_eq!(sa.sin6_scope_id, index),
+        _ => panic!("expected an IPv6 address"),
+    }
+
+    assert!(InetAddr::from_str(&format!("[fe80::1%{}]:80", index)).unwrap() ==
+            InetAddr::from_str("[fe80::1%lo]:80").unwrap());
+}
+
+#[test]
+pub fn test_ip_proto() {
+    use nix::sys::socket::{socket_with_proto, IpProto};
+
+    assert_eq!(IpProto::Tcp.as_c_int(), 6);
+    assert_eq!(IpProto::Udp.as_c_int(), 17);
+    assert_eq!(IpProto::Icmp.as_c_int(), 1);
+
+    let fd = socket_with_proto(AddressFamily::Inet, SockType::Stream, SockFlag::empty(), IpProto::Tcp).unwrap();
+    close(fd).unwrap();
+
+    // Raw sockets need CAP_NET_RAW
+    match socket_with_proto(AddressFamily::Inet, SockType::Raw, SockFlag::empty(), IpProto::Icmp) {
+        Ok(fd) => close(fd).unwrap(),
+        Err(Error::Sys(Errno::EPERM)) | Err(Error::Sys(Errno::EACCES)) => {}
+        Err(e) => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_getsockopt_domain_and_protocol() {
+    use nix::sys::socket::IPPROTO_TCP;
+
+    let fd = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+
+    assert_eq!(getsockopt(fd, SockLevel::Socket, sockopt::SocketDomain).unwrap(), Some(AddressFamily::Inet));
+    assert_eq!(getsockopt(fd, SockLevel::Socket, sockopt::SocketProtocol).unwrap(), IPPROTO_TCP);
+
+    close(fd).unwrap();
+}
+
+#[test]
+pub fn test_inet_addr_ord() {
+    use std::collections::BTreeSet;
+
+    let mut set = BTreeSet::new();
+
+    for s in ["[::1]:80", "10.0.0.1:80", "127.0.0.1:443", "127.0.0.1:80", "[::]:8080", "9.255.255.255:80"].iter() {
+        set.insert(InetAddr::from_str(s).unwrap());
+    }
+
+    let order: Vec<String> = set.iter().map(|addr| addr.to_str()).collect();
+    assert_eq!(order, ["9.255.255.255:80", "10.0.0.1:80", "127.0.0.1:80", "127.0.0.1:443", "[::]:8080", "[::1]:80"]);
+}
+
+#[test]
+pub fn test_sendmsg_slices() {
+    use nix::sys::socket::sendmsg_slices;
+    use nix::unistd::read;
+
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Stream, SockFlag::empty()).unwrap();
+
+    let bufs: [&[u8]; 4] = [b"one", b"two", b"three", b"four"];
+    assert_eq!(sendmsg_slices(a, &bufs, &[], 0, None).unwrap(), 15);
+
+    let mut buf = [0u8; 15];
+    let mut len = 0;
+    while len < buf.len() {
+        len += read(b, &mut buf[len..]).unwrap();
+    }
+    assert_eq!(&buf[..], &b"onetwothreefour"[..]);
+
+    // More buffers than fit on the stack
+    let many: Vec<&[u8]> = (0..20).map(|_| &b"x"[..]).collect();
+    assert_eq!(sendmsg_slices(a, &many, &[], 0, None).unwrap(), 20);
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
+
+#[test]
+pub fn test_inet_addr_predicates() {
+    let addr = |s: &str| InetAddr::from_str(s).unwrap();
+
+    assert!(addr("127.0.0.1:80").is_loopback());
+    assert!(addr("[::1]:80").is_loopback());
+    assert!(!addr("10.0.0.1:80").is_loopback());
+
+    assert!(addr("224.0.0.1:80").is_multicast());
+    assert!(addr("[ff02::1]:80").is_multicast());
+    assert!(!addr("127.0.0.1:80").is_multicast());
+
+    assert!(addr("0.0.0.0:80").is_unspecified());
+    assert!(addr("[::]:80").is_unspecified());
+    assert!(!addr("[::1]:80").is_unspecified());
+
+    assert!(addr("[fe80::1]:80").is_link_local());
+    assert!(addr("169.254.1.1:80").is_link_local());
+    assert!(!addr("[fec0::1]:80").is_link_local());
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_bind_to_device() {
+    let fd = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+
+    match setsockopt(fd, SockLevel::Socket, sockopt::BindToDevice, "lo") {
+        Ok(()) => {
+            assert_eq!(getsockopt(fd, SockLevel::Socket, sockopt::BindToDevice).unwrap(), "lo");
+        }
+        // Requires CAP_NET_RAW
+        Err(Error::Sys(Errno::EPERM)) => {}
+        Err(e) => panic!("unexpected error: {:?}", e),
+    }
+
+    close(fd).unwrap();
+}
+
+// With a full accept queue the kernel drops the SYN, so `connect` blocks
+// until it is retransmitted about a second later. A SIGALRM arriving in the
+// meantime, without SA_RESTART, interrupts it.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_connect_interrupted() {
+    use nix::sys::pthread::{pthread_self, Pthread};
+    use nix::sys::signal::{self, sigaction, SigAction, SigSet, SIGALRM};
+    use nix::sys::socket::{accept, bind_ephemeral, connect, getpeername};
+    use libc::c_int;
+    use std::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
+    use std::thread;
+
+    extern {
+        fn pthread_kill(thread: Pthread, sig: c_int) -> c_int;
+    }
+
+    static CAUGHT: AtomicBool = ATOMIC_BOOL_INIT;
+
+    extern fn record_sigalrm(_: c_int) {
+        CAUGHT.store(true, Ordering::SeqCst);
+    }
+
+    let listener = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    let addr = SockAddr::Inet(bind_ephemeral(listener, IpAddr::new_v4(127, 0, 0, 1)).unwrap());
+    listen(listener, 0).unwrap();
+
+    // Fills the accept queue
+    let first = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    connect(first, &addr).unwrap();
+
+    let action = SigAction::new(record_sigalrm, signal::SockFlag::empty(), SigSet::empty());
+    let prev = sigaction(SIGALRM, &action).unwrap();
+
+    let main = pthread_self();
+    let helper = thread::spawn(move || {
+        thread::sleep_ms(200);
+        assert_eq!(unsafe { pthread_kill(main, SIGALRM) }, 0);
+        thread::sleep_ms(200);
+
+        // Make room for the retransmitted SYN
+        let conn = accept(listener).unwrap();
+        close(conn).unwrap();
+    });
+
+    let second = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    connect(second, &addr).unwrap();
+    helper.join().unwrap();
+
+    assert!(CAUGHT.load(Ordering::SeqCst));
+    assert!(getpeername(second).unwrap() == addr);
+
+    sigaction(SIGALRM, &prev).unwrap();
+
+    close(second).unwrap();
+    close(first).unwrap();
+    close(listener).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_tcp_defer_accept() {
+    use nix::sys::socket::bind_ephemeral;
+
+    let fd = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    bind_ephemeral(fd, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+    listen(fd, 1).unwrap();
+
+    setsockopt(fd, SockLevel::Tcp, sockopt::TcpDeferAccept, 5).unwrap();
+    assert!(getsockopt(fd, SockLevel::Tcp, sockopt::TcpDeferAccept).unwrap() >= 5);
+
+    setsockopt(fd, SockLevel::Tcp, sockopt::TcpDeferAccept, 0).unwrap();
+    assert_eq!(getsockopt(fd, SockLevel::Tcp, sockopt::TcpDeferAccept).unwrap(), 0);
+
+    close(fd).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_tcp_info() {
+    use nix::sys::socket::{accept, bind_ephemeral, connect, TCP_ESTABLISHED, TCP_LISTEN};
+
+    let listener = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    let addr = bind_ephemeral(listener, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+    listen(listener, 1).unwrap();
+
+    let client = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    connect(client, &SockAddr::Inet(addr)).unwrap();
+    let server = accept(listener).unwrap();
+
+    let info = getsockopt(client, SockLevel::Tcp, sockopt::TcpInfo).unwrap();
+    assert_eq!(info.tcpi_state, TCP_ESTABLISHED);
+    assert!(info.tcpi_snd_cwnd > 0);
+    assert_eq!(info.tcpi_total_retrans, 0);
+
+    let info = getsockopt(listener, SockLevel::Tcp, sockopt::TcpInfo).unwrap();
+    assert_eq!(info.tcpi_state, TCP_LISTEN);
+
+    close(server).unwrap();
+    close(client).unwrap();
+    close(listener).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_bind_checked() {
+    use nix::sys::socket::bind_checked;
+
+    let fd = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+
+    let v6 = SockAddr::Inet(InetAddr::new(IpAddr::new_v6(0, 0, 0, 0, 0, 0, 0, 1), 0));
+    match bind_checked(fd, &v6) {
+        Err(Error::Sys(Errno::EAFNOSUPPORT)) => {}
+        res => panic!("unexpected result: {:?}", res),
+    }
+
+    let v4 = SockAddr::Inet(InetAddr::new(IpAddr::new_v4(127, 0, 0, 1), 0));
+    bind_checked(fd, &v4).unwrap();
+
+    close(fd).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_netlink_bind() {
+    use nix::sys::socket::bind;
+
+    let fd = match socket(AddressFamily::Netlink, SockType::Raw, SockFlag::empty()) {
+        Ok(fd) => fd,
+        // Netlink may be unavailable or forbidden in a sandbox
+        Err(Error::Sys(Errno::EAFNOSUPPORT)) | Err(Error::Sys(Errno::EPERM)) => return,
+        Err(e) => panic!("unexpected error: {:?}", e),
+    };
+
+    bind(fd, &SockAddr::new_netlink(0, 0)).unwrap();
+
+    match getsockname(fd).unwrap() {
+        // The kernel assigns a unique port id when binding to 0
+        SockAddr::Netlink(addr) => {
+            assert!(addr.pid() != 0);
+            assert_eq!(addr.groups(), 0);
+        }
+        addr => panic!("unexpected address: {}", addr),
+    }
+
+    close(fd).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_vsock_addr() {
+    use nix::sys::socket::{VsockAddr, VMADDR_CID_ANY, VMADDR_CID_HOST};
+
+    let addr = VsockAddr::new(VMADDR_CID_HOST, 1234);
+    assert_eq!(addr.cid(), 2);
+    assert_eq!(addr.port(), 1234);
+    assert_eq!(addr.to_string(), "cid: 2 port: 1234");
+
+    let any = SockAddr::new_vsock(VMADDR_CID_ANY, 1234);
+    assert_eq!(any.family(), AddressFamily::Vsock);
+    assert!(any != SockAddr::Vsock(addr));
+
+    match any {
+        SockAddr::Vsock(addr) => assert_eq!(addr.cid(), VMADDR_CID_ANY),
+        _ => panic!("expected a vsock address"),
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_seqpacket_message_boundaries() {
+    use nix::sys::socket::recv;
+
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::SeqPacket, SockFlag::empty()).unwrap();
+    assert_eq!(getsockopt(a, SockLevel::Socket, sockopt::SocketType).unwrap(), Some(SockType::SeqPacket));
+
+    assert_eq!(send(a, b"first", 0).unwrap(), 5);
+    assert_eq!(send(a, b"second", 0).unwrap(), 6);
+
+    // Each message is returned on its own, even though both would fit
+    let mut buf = [0u8; 32];
+    assert_eq!(recv(b, &mut buf, 0).unwrap(), 5);
+    assert_eq!(&buf[..5], &b"first"[..]);
+    assert_eq!(recv(b, &mut buf, 0).unwrap(), 6);
+    assert_eq!(&buf[..6], &b"second"[..]);
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
+
+#[test]
+pub fn test_inet_addr_storage_round_trip() {
+    for s in ["127.0.0.1:3000", "[fe80::1%2]:80"].iter() {
+        let addr = InetAddr::from_str(s).unwrap();
+        let (storage, len) = addr.to_storage();
+
+        assert_eq!(len as usize, match addr {
+            InetAddr::V4(..) => mem::size_of::<sockaddr_in>(),
+            InetAddr::V6(..) => mem::size_of::<sockaddr_in6>(),
+        });
+        assert!(InetAddr::from_storage(&storage).unwrap() == addr);
+    }
+
+    let storage: sockaddr_storage = unsafe { mem::zeroed() };
+    assert!(InetAddr::from_storage(&storage).is_none());
+}
+
+#[test]
+pub fn test_incoming() {
+    use nix::sys::socket::{bind_ephemeral, connect, incoming};
+
+    let listener = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    let addr = bind_ephemeral(listener, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+    listen(listener, 3).unwrap();
+
+    let clients: Vec<_> = (0..3).map(|_| {
+        let fd = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+        connect(fd, &SockAddr::Inet(addr)).unwrap();
+        fd
+    }).collect();
+
+    let accepted: Vec<_> = incoming(listener).take(3).map(|conn| conn.unwrap()).collect();
+
+    for (&client, &(fd, ref peer)) in clients.iter().zip(accepted.iter()) {
+        assert!(getsockname(client).unwrap() == *peer);
+        close(fd).unwrap();
+        close(client).unwrap();
+    }
+
+    close(listener).unwrap();
+}
+
+#[test]
+pub fn test_oob_data() {
+    use nix::sys::socket::{accept, bind_ephemeral, connect, recv, MSG_OOB};
+
+    let listener = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    let addr = bind_ephemeral(listener, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+    listen(listener, 1).unwrap();
+
+    let client = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    connect(client, &SockAddr::Inet(addr)).unwrap();
+    let server = accept(listener).unwrap();
+
+    assert!(!getsockopt(server, SockLevel::Socket, sockopt::OobInline).unwrap());
+
+    assert_eq!(send(client, b"ab", 0).unwrap(), 2);
+    assert_eq!(send(client, b"!", MSG_OOB).unwrap(), 1);
+
+    // Normal reads stop at the urgent mark, by which time the OOB byte is
+    // available
+    let mut buf = [0u8; 8];
+    assert_eq!(recv(server, &mut buf, 0).unwrap(), 2);
+    assert_eq!(&buf[..2], &b"ab"[..]);
+    assert_eq!(recv(server, &mut buf, MSG_OOB).unwrap(), 1);
+    assert_eq!(buf[0], b'!');
+
+    setsockopt(server, SockLevel::Socket, sockopt::OobInline, true).unwrap();
+    assert!(getsockopt(server, SockLevel::Socket, sockopt::OobInline).unwrap());
+
+    close(server).unwrap();
+    close(client).unwrap();
+    close(listener).unwrap();
+}
+
+#[test]
+pub fn test_socket_read_write() {
+    use nix::sys::socket::Socket;
+    use std::io::{BufRead, BufReader, Write};
+
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Stream, SockFlag::empty()).unwrap();
+
+    let mut writer = Socket(a);
+    writer.write_all(b"hello world\n").unwrap();
+    writer.flush().unwrap();
+
+    let mut line = String::new();
+    BufReader::new(Socket(b)).read_line(&mut line).unwrap();
+    assert_eq!(line, "hello world\n");
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
+
+#[test]
+pub fn test_socket_read_eof() {
+    use nix::sys::socket::{shutdown, Shutdown, Socket};
+    use std::io::Read;
+
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Stream, SockFlag::empty()).unwrap();
+
+    assert_eq!(send(a, b"bye", 0).unwrap(), 3);
+    shutdown(a, Shutdown::Write).unwrap();
+
+    let mut data = Vec::new();
+    assert_eq!(Socket(b).read_to_end(&mut data).unwrap(), 3);
+    assert_eq!(&data[..], &b"bye"[..]);
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
+
+#[test]
+pub fn test_socket_read_reset() {
+    use nix::sys::socket::{accept, bind_ephemeral, connect, linger, Socket};
+    use std::io::{ErrorKind, Read};
+
+    let listener = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    let addr = bind_ephemeral(listener, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+    listen(listener, 1).unwrap();
+
+    let client = socket(AddressFamily::Inet, SockType::Stream, SockFlag::empty()).unwrap();
+    connect(client, &SockAddr::Inet(addr)).unwrap();
+    let server = accept(listener).unwrap();
+
+    // Closing with a zero linger timeout resets the connection
+    setsockopt(server, SockLevel::Socket, sockopt::Linger, &linger { l_onoff: 1, l_linger: 0 }).unwrap();
+    close(server).unwrap();
+
+    let mut buf = [0u8; 8];
+    let err = Socket(client).read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+    assert_eq!(err.raw_os_error(), Some(Errno::ECONNRESET as i32));
+
+    close(client).unwrap();
+    close(listener).unwrap();
+}
+
+#[test]
+pub fn test_inet_addr_same_ip() {
+    let a = InetAddr::from_str("[fe80::1%1]:80").unwrap();
+    let b = InetAddr::from_str("[fe80::1%2]:80").unwrap();
+
+    assert!(a.same_ip(&b));
+    assert!(a != b);
+
+    assert!(!a.same_ip(&InetAddr::from_str("[fe80::1%1]:81").unwrap()));
+    assert!(!a.same_ip(&InetAddr::from_str("127.0.0.1:80").unwrap()));
+}
+
+#[test]
+pub fn test_sock_addr_as_bytes() {
+    let addr = SockAddr::new_inet(InetAddr::new(IpAddr::new_v4(127, 0, 0, 1), 0x1234));
+    let bytes = addr.as_bytes();
+
+    assert_eq!(bytes.len(), addr.len() as usize);
+    // The port follows the family, in network byte order
+    assert_eq!(&bytes[2..4], &[0x12, 0x34][..]);
+    assert_eq!(&bytes[4..8], &[127, 0, 0, 1][..]);
+    assert_eq!(addr.as_ref(), bytes);
+}
+
+#[test]
+pub fn test_sock_addr_to_std() {
+    let actual: net::SocketAddr = FromStr::from_str("127.0.0.1:3000").unwrap();
+    let addr = SockAddr::new_inet(InetAddr::from_std(&actual));
+    assert_eq!(addr.to_std(), Some(actual));
+
+    let addr = SockAddr::new_unix(Path::new("/tmp/nix-test.sock")).unwrap();
+    assert_eq!(addr.to_std(), None);
+}
+
+#[test]
+pub fn test_inet_addr_reverse_dns_name() {
+    let addr = InetAddr::from_str("1.2.3.4:0").unwrap();
+    assert_eq!(addr.reverse_dns_name(), "4.3.2.1.in-addr.arpa");
+
+    let addr = InetAddr::from_str("[2001:db8::567:89ab]:0").unwrap();
+    assert_eq!(addr.reverse_dns_name(),
+               "b.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa");
+}
+
+#[test]
+pub fn test_inet_addr_same_host() {
+    let a = InetAddr::from_str("127.0.0.1:80").unwrap();
+    let b = InetAddr::from_str("127.0.0.1:443").unwrap();
+
+    assert!(a.same_host(&b));
+    assert!(a != b);
+
+    assert!(InetAddr::from_str("[fe80::1%1]:80").unwrap()
+            .same_host(&InetAddr::from_str("[fe80::1%2]:443").unwrap()));
+    assert!(!a.same_host(&InetAddr::from_str("127.0.0.2:80").unwrap()));
+    assert!(!a.same_host(&InetAddr::from_str("[::ffff:127.0.0.1]:80").unwrap()));
+}
+
+#[test]
+pub fn test_ip_tos() {
+    let fd = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+
+    setsockopt(fd, SockLevel::Ip, sockopt::IpTos, 0x10).unwrap();
+    assert_eq!(getsockopt(fd, SockLevel::Ip, sockopt::IpTos).unwrap(), 0x10);
+
+    close(fd).unwrap();
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn test_snd_buf_force() {
+    let fd = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+
+    // Well above the default net.core.wmem_max
+    let size = 16 * 1024 * 1024;
+
+    match setsockopt(fd, SockLevel::Socket, sockopt::SndBufForce, size) {
+        Ok(()) => assert!(getsockopt(fd, SockLevel::Socket, sockopt::SndBuf).unwrap() >= size),
+        // Needs CAP_NET_ADMIN
+        Err(Error::Sys(Errno::EPERM)) => {}
+        Err(e) => panic!("unexpected error {:?}", e),
+    }
+
+    close(fd).unwrap();
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn test_priority() {
+    let fd = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+
+    setsockopt(fd, SockLevel::Socket, sockopt::Priority, 6).unwrap();
+    assert_eq!(getsockopt(fd, SockLevel::Socket, sockopt::Priority).unwrap(), 6);
+
+    close(fd).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_recvmmsg_partial() {
+    use nix::sys::socket::{bind_ephemeral, recvmmsg, sendmmsg, MSG_WAITFORONE};
+
+    let rx = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let addr = SockAddr::Inet(bind_ephemeral(rx, IpAddr::new_v4(127, 0, 0, 1)).unwrap());
+
+    let tx = socket(AddressFamily::Inet, SockType::Datagram, SockFlag::empty()).unwrap();
+    let sender = SockAddr::Inet(bind_ephemeral(tx, IpAddr::new_v4(127, 0, 0, 1)).unwrap());
+    assert_eq!(sendmmsg(tx, &[&b"one"[..], &b"two!"[..]], Some(&addr), 0).unwrap(), 2);
+
+    let mut bufs = [[0u8; 8]; 5];
+    let received = {
+        let mut slices: Vec<&mut [u8]> = bufs.iter_mut().map(|b| &mut b[..]).collect();
+        recvmmsg(rx, &mut slices, MSG_WAITFORONE, Some(TimeSpec::milliseconds(100))).unwrap()
+    };
+
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[0].bytes, 3);
+    assert_eq!(received[1].bytes, 4);
+    assert!(received[0].address == Some(sender));
+    assert_eq!(&bufs[0][..3], &b"one"[..]);
+    assert_eq!(&bufs[1][..4], &b"two!"[..]);
+
+    close(tx).unwrap();
+    close(rx).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+pub fn test_recvmmsg_empty() {
+    use nix::sys::socket::{bind_ephemeral, recvmmsg, SOCK_NONBLOCK};
+
+    let rx = socket(AddressFamily::Inet, SockType::Datagram, SOCK_NONBLOCK).unwrap();
+    bind_ephemeral(rx, IpAddr::new_v4(127, 0, 0, 1)).unwrap();
+
+    let mut bufs = [[0u8; 8]; 5];
+    let mut slices: Vec<&mut [u8]> = bufs.iter_mut().map(|b| &mut b[..]).collect();
+    assert_eq!(recvmmsg(rx, &mut slices, 0, None).unwrap().len(), 0);
+
+    close(rx).unwrap();
+}
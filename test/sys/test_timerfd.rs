@@ -0,0 +1,27 @@
+use nix::sys::timerfd::*;
+use nix::sys::time::TimeSpec;
+use nix::unistd::{close, read};
+use std::mem;
+
+#[test]
+fn test_timerfd_one_shot() {
+    let fd = timerfd_create(ClockId::Monotonic, TFD_CLOEXEC).unwrap();
+
+    let old = timerfd_settime(fd, TimerSetTimeFlag::empty(), &ITimerSpec::one_shot(TimeSpec::milliseconds(50))).unwrap();
+    assert_eq!(old.value, TimeSpec::zero());
+
+    let curr = timerfd_gettime(fd).unwrap();
+    assert_eq!(curr.interval, TimeSpec::zero());
+    assert!(curr.value > TimeSpec::zero());
+
+    // Blocks until the timer fires
+    let mut buf = [0u8; 8];
+    assert_eq!(read(fd, &mut buf).unwrap(), 8);
+    let expirations: u64 = unsafe { mem::transmute(buf) };
+    assert_eq!(expirations, 1);
+
+    // A one-shot timer is disarmed once it has fired
+    assert_eq!(timerfd_gettime(fd).unwrap().value, TimeSpec::zero());
+
+    close(fd).unwrap();
+}
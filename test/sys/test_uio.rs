@@ -90,3 +90,20 @@ fn test_readv() {
     let close_res = close(writer);
     assert!(close_res.is_ok());
 }
+
+#[test]
+fn test_writev_socketpair() {
+    use nix::sys::socket::{socketpair, AddressFamily, SockFlag, SockType};
+
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Stream, SockFlag::empty()).unwrap();
+
+    let iov = [IoVec::from_slice(b"header\n"), IoVec::from_slice(b"body")];
+    assert_eq!(writev(a, &iov).unwrap(), 11);
+
+    let mut buf = [0u8; 16];
+    assert_eq!(read(b, &mut buf).unwrap(), 11);
+    assert_eq!(&buf[..11], &b"header\nbody"[..]);
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
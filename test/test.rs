@@ -3,7 +3,9 @@ extern crate libc;
 extern crate rand;
 
 mod sys;
+mod test_fcntl;
 mod test_nix_path;
+mod test_poll;
 mod test_stat;
 mod test_unistd;
 
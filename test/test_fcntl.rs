@@ -0,0 +1,17 @@
+use nix::fcntl::{is_cloexec, set_cloexec};
+use nix::sys::socket::{socket, AddressFamily, SockType, SockFlag};
+use nix::unistd::close;
+
+#[test]
+fn test_set_cloexec() {
+    let fd = socket(AddressFamily::Unix, SockType::Stream, SockFlag::empty()).unwrap();
+    assert!(!is_cloexec(fd).unwrap());
+
+    set_cloexec(fd, true).unwrap();
+    assert!(is_cloexec(fd).unwrap());
+
+    set_cloexec(fd, false).unwrap();
+    assert!(!is_cloexec(fd).unwrap());
+
+    close(fd).unwrap();
+}
@@ -0,0 +1,16 @@
+use nix::poll::*;
+use nix::sys::socket::{socketpair, AddressFamily, SockType, SockFlag};
+use nix::unistd::close;
+
+#[test]
+fn test_poll_writable() {
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Stream, SockFlag::empty()).unwrap();
+    let mut fds = [PollFd::new(a, POLLOUT)];
+
+    // A fresh socket has an empty send buffer, so it is writable straight away
+    assert_eq!(poll(&mut fds, 0).unwrap(), 1);
+    assert!(fds[0].revents.contains(POLLOUT));
+
+    close(a).unwrap();
+    close(b).unwrap();
+}
@@ -70,3 +70,47 @@ fn test_execve() {
         }
     }
 }
+
+#[test]
+fn test_owned_fd() {
+    use nix::sys::socket::{socketpair, AddressFamily, SockFlag, SockType};
+
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Stream, SockFlag::empty()).unwrap();
+    let owned = OwnedFd::new(a);
+    assert_eq!(owned.as_raw_fd(), a);
+
+    // Once the end is closed the peer reads end of file
+    drop(owned);
+    let mut buf = [0u8; 1];
+    assert_eq!(read(b, &mut buf).unwrap(), 0);
+
+    let fd = OwnedFd::new(b).into_raw_fd();
+    close(fd).unwrap();
+}
+
+#[test]
+fn test_dup_socket() {
+    use nix::fcntl::{is_cloexec, O_CLOEXEC};
+    use nix::sys::socket::{socketpair, AddressFamily, SockFlag, SockType};
+
+    let (a, b) = socketpair(AddressFamily::Unix, SockType::Stream, SockFlag::empty()).unwrap();
+    let d = dup(b).unwrap();
+    assert!(d != b);
+
+    // Both descriptors refer to the same socket
+    let mut buf = [0u8; 5];
+    write(a, b"hello").unwrap();
+    assert_eq!(read(d, &mut buf).unwrap(), 5);
+    assert_eq!(&buf, b"hello");
+    write(a, b"world").unwrap();
+    assert_eq!(read(b, &mut buf).unwrap(), 5);
+    assert_eq!(&buf, b"world");
+
+    assert_eq!(dup3(b, d, O_CLOEXEC).unwrap(), d);
+    assert!(is_cloexec(d).unwrap());
+    assert!(!is_cloexec(b).unwrap());
+
+    close(d).unwrap();
+    close(a).unwrap();
+    close(b).unwrap();
+}